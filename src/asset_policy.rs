@@ -0,0 +1,287 @@
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use url::Url;
+
+/// Kind of static asset, used to enable or disable warming per type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AssetType {
+    /// CSS stylesheets, including `@import`ed ones
+    Stylesheet,
+    /// JavaScript files and module preloads
+    Script,
+    /// Images, icons, posters and share images
+    Image,
+    /// Web fonts
+    Font,
+    /// Video, audio and text tracks
+    Media,
+    /// Other documents: web manifests, PDFs, JSON/XML fetch preloads
+    Document,
+}
+
+impl AssetType {
+    pub const ALL: [AssetType; 6] = [
+        AssetType::Stylesheet,
+        AssetType::Script,
+        AssetType::Image,
+        AssetType::Font,
+        AssetType::Media,
+        AssetType::Document,
+    ];
+
+    /// Lowercase name, as used on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            AssetType::Stylesheet => "stylesheet",
+            AssetType::Script => "script",
+            AssetType::Image => "image",
+            AssetType::Font => "font",
+            AssetType::Media => "media",
+            AssetType::Document => "document",
+        }
+    }
+
+    /// Guess the type from the URL's file extension.
+    pub fn from_url(url: &str) -> Option<Self> {
+        let path = Url::parse(url).ok()?.path().to_lowercase();
+        let ext = path.rsplit_once('.')?.1;
+        let kind = match ext {
+            "css" => AssetType::Stylesheet,
+            "js" | "mjs" => AssetType::Script,
+            "png" | "jpg" | "jpeg" | "gif" | "webp" | "avif" | "svg" | "ico" | "bmp" => {
+                AssetType::Image
+            }
+            "woff" | "woff2" | "ttf" | "otf" | "eot" => AssetType::Font,
+            "mp4" | "webm" | "ogv" | "mov" | "m3u8" | "mpd" | "mp3" | "ogg" | "oga" | "wav"
+            | "m4a" | "aac" | "flac" | "vtt" => AssetType::Media,
+            "pdf" | "json" | "webmanifest" | "xml" | "txt" => AssetType::Document,
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    /// Type of a `<link>` target from its `rel` (and `as` for preloads).
+    /// Returns `None` for links that are not assets: canonical, alternate, feeds,
+    /// pagination, preconnect hints and the like.
+    pub fn from_link(rel: &str, as_attr: Option<&str>, href: &str) -> Option<Self> {
+        let rel = rel.to_lowercase();
+        let tokens: Vec<&str> = rel.split_whitespace().collect();
+        if tokens.contains(&"stylesheet") {
+            return Some(AssetType::Stylesheet);
+        }
+        if tokens.contains(&"modulepreload") {
+            return Some(AssetType::Script);
+        }
+        if tokens
+            .iter()
+            .any(|t| matches!(*t, "icon" | "apple-touch-icon" | "mask-icon"))
+        {
+            return Some(AssetType::Image);
+        }
+        if tokens.contains(&"manifest") {
+            return Some(AssetType::Document);
+        }
+        if tokens.contains(&"preload") || tokens.contains(&"prefetch") {
+            return match as_attr.map(|a| a.to_lowercase()).as_deref() {
+                Some("style") => Some(AssetType::Stylesheet),
+                Some("script") | Some("worker") => Some(AssetType::Script),
+                Some("image") => Some(AssetType::Image),
+                Some("font") => Some(AssetType::Font),
+                Some("audio") | Some("video") | Some("track") => Some(AssetType::Media),
+                Some("fetch") | Some("document") => Some(AssetType::Document),
+                _ => Self::from_url(href),
+            };
+        }
+        None
+    }
+}
+
+/// Which cross-origin hosts assets may be fetched from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThirdPartyMode {
+    /// Only fetch assets from the page's own host and its subdomains
+    Skip,
+    /// Also fetch assets from the `--cdn-host` allowlist
+    Allowlist,
+    /// Fetch assets from any host
+    All,
+}
+
+/// Asset type and third-party host policy applied to every discovered asset.
+pub struct AssetPolicy {
+    types: Vec<AssetType>,
+    third_party: ThirdPartyMode,
+    cdn_hosts: Vec<String>,
+    /// Every distinct third-party asset skipped, for the summary count.
+    skipped_third_party: Mutex<HashSet<String>>,
+}
+
+impl AssetPolicy {
+    pub fn new(types: &[AssetType], third_party: ThirdPartyMode, cdn_hosts: &[String]) -> Self {
+        Self {
+            types: types.to_vec(),
+            third_party,
+            cdn_hosts: cdn_hosts.iter().map(|h| h.to_lowercase()).collect(),
+            skipped_third_party: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Whether an asset of `kind` found on `page_url` should be warmed.
+    pub fn allows(&self, asset_url: &str, kind: AssetType, page_url: &str) -> bool {
+        if !self.types.contains(&kind) {
+            return false;
+        }
+        if self.third_party == ThirdPartyMode::All || is_first_party(asset_url, page_url) {
+            return true;
+        }
+        if self.third_party == ThirdPartyMode::Allowlist && self.is_cdn_host(asset_url) {
+            return true;
+        }
+        self.skipped_third_party
+            .lock()
+            .unwrap()
+            .insert(asset_url.to_string());
+        false
+    }
+
+    /// Whether an asset the site lists itself (sitemap image/video entries, feed
+    /// enclosures) should be warmed. Only the type filter applies: the site named the
+    /// URL, so its host is trusted whatever the third-party mode.
+    pub fn allows_listed(&self, kind: AssetType) -> bool {
+        self.types.contains(&kind)
+    }
+
+    pub fn third_party_skipped(&self) -> usize {
+        self.skipped_third_party.lock().unwrap().len()
+    }
+
+    /// Allowlisted hosts match exactly or as a parent domain (fonts.example.net
+    /// matches an allowlisted example.net).
+    fn is_cdn_host(&self, asset_url: &str) -> bool {
+        let Some(host) = host_of(asset_url) else {
+            return false;
+        };
+        self.cdn_hosts
+            .iter()
+            .any(|cdn| host == *cdn || host.ends_with(&format!(".{}", cdn)))
+    }
+}
+
+/// Same host as the page, or a subdomain of it (ignoring a leading `www.` on the page host,
+/// so static.example.com counts as first-party for www.example.com).
+fn is_first_party(asset_url: &str, page_url: &str) -> bool {
+    let (Some(asset_host), Some(page_host)) = (host_of(asset_url), host_of(page_url)) else {
+        return false;
+    };
+    let base = page_host.strip_prefix("www.").unwrap_or(&page_host);
+    asset_host == page_host || asset_host == base || asset_host.ends_with(&format!(".{}", base))
+}
+
+fn host_of(url: &str) -> Option<String> {
+    Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn link_rel_decides_the_type() {
+        let link = |rel, href| AssetType::from_link(rel, None, href);
+        assert_eq!(link("stylesheet", "/s"), Some(AssetType::Stylesheet));
+        assert_eq!(
+            link("Alternate Stylesheet", "/s"),
+            Some(AssetType::Stylesheet)
+        );
+        assert_eq!(link("modulepreload", "/m"), Some(AssetType::Script));
+        assert_eq!(link("shortcut icon", "/favicon"), Some(AssetType::Image));
+        assert_eq!(link("apple-touch-icon", "/t.png"), Some(AssetType::Image));
+        assert_eq!(link("manifest", "/site"), Some(AssetType::Document));
+        for rel in [
+            "canonical",
+            "alternate",
+            "next",
+            "preconnect",
+            "dns-prefetch",
+        ] {
+            assert_eq!(link(rel, "https://example.com/a.css"), None, "{}", rel);
+        }
+    }
+
+    #[test]
+    fn preloads_are_typed_by_as() {
+        let preload = |as_attr, href| AssetType::from_link("preload", as_attr, href);
+        assert_eq!(preload(Some("style"), "/x"), Some(AssetType::Stylesheet));
+        assert_eq!(preload(Some("Script"), "/x"), Some(AssetType::Script));
+        assert_eq!(preload(Some("worker"), "/x"), Some(AssetType::Script));
+        assert_eq!(preload(Some("image"), "/x"), Some(AssetType::Image));
+        assert_eq!(preload(Some("font"), "/x"), Some(AssetType::Font));
+        assert_eq!(preload(Some("track"), "/x"), Some(AssetType::Media));
+        assert_eq!(preload(Some("fetch"), "/x"), Some(AssetType::Document));
+        assert_eq!(
+            AssetType::from_link("prefetch", Some("video"), "/x"),
+            Some(AssetType::Media)
+        );
+        // Without a known `as`, fall back to the extension
+        assert_eq!(
+            preload(None, "https://example.com/f.woff2"),
+            Some(AssetType::Font)
+        );
+        assert_eq!(preload(Some("object"), "https://example.com/x"), None);
+    }
+
+    #[test]
+    fn first_party_covers_the_host_and_its_subdomains() {
+        let page = "https://www.example.com/blog/";
+        assert!(is_first_party("https://www.example.com/a.css", page));
+        assert!(is_first_party("https://example.com/a.css", page));
+        assert!(is_first_party("https://static.EXAMPLE.com/a.css", page));
+        assert!(is_first_party("http://img.cdn.example.com/a.png", page));
+        assert!(!is_first_party("https://example.net/a.css", page));
+        assert!(!is_first_party("https://notexample.com/a.css", page));
+        assert!(!is_first_party("https://example.com.evil.net/a.css", page));
+    }
+
+    #[test]
+    fn third_party_modes() {
+        let cdn = ["example.net".to_string()];
+        let page = "https://example.com/";
+        let font = "https://fonts.example.net/f.woff2";
+        let other = "https://tracker.test/pixel.gif";
+
+        let all = AssetPolicy::new(&AssetType::ALL, ThirdPartyMode::All, &[]);
+        assert!(all.allows(other, AssetType::Image, page));
+
+        let allowlist = AssetPolicy::new(&AssetType::ALL, ThirdPartyMode::Allowlist, &cdn);
+        assert!(allowlist.allows(font, AssetType::Font, page));
+        assert!(!allowlist.allows(other, AssetType::Image, page));
+
+        let skip = AssetPolicy::new(&AssetType::ALL, ThirdPartyMode::Skip, &cdn);
+        assert!(skip.allows("https://static.example.com/a.js", AssetType::Script, page));
+        assert!(!skip.allows(font, AssetType::Font, page));
+        assert!(!skip.allows(other, AssetType::Image, page));
+        assert_eq!(skip.third_party_skipped(), 2);
+    }
+
+    #[test]
+    fn disabled_types_are_never_allowed() {
+        let policy = AssetPolicy::new(&[AssetType::Image], ThirdPartyMode::All, &[]);
+        assert!(policy.allows(
+            "https://example.com/a.png",
+            AssetType::Image,
+            "https://example.com/"
+        ));
+        assert!(!policy.allows(
+            "https://example.com/a.js",
+            AssetType::Script,
+            "https://example.com/"
+        ));
+        assert!(policy.allows_listed(AssetType::Image));
+        assert!(!policy.allows_listed(AssetType::Media));
+    }
+}
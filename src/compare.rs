@@ -0,0 +1,302 @@
+use crate::results::{Latency, RunResults};
+use colored::Colorize;
+
+/// Latency changes smaller than this are noise, whatever their relative size.
+const MIN_LATENCY_DELTA_MS: f64 = 5.0;
+
+/// At most this many regressed URLs are listed.
+const MAX_LISTED_URLS: usize = 10;
+
+/// How much worse than the baseline a run may be before it counts as a regression.
+#[derive(Clone, Copy, Debug)]
+pub struct Tolerance {
+    /// Allowed drop in throughput and rise in latency, in percent
+    pub percent: f64,
+    /// Allowed rise in error rate, in percentage points
+    pub error_rate: f64,
+}
+
+/// Which way a figure is better.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Better {
+    Higher,
+    Lower,
+}
+
+struct Row {
+    name: &'static str,
+    baseline: f64,
+    current: f64,
+    unit: &'static str,
+    regressed: bool,
+}
+
+impl Row {
+    /// A throughput or latency figure, compared by relative change.
+    fn relative(
+        name: &'static str,
+        baseline: f64,
+        current: f64,
+        unit: &'static str,
+        better: Better,
+        tolerance: Tolerance,
+    ) -> Self {
+        let limit = tolerance.percent / 100.0;
+        let regressed = match better {
+            Better::Higher => current < baseline * (1.0 - limit),
+            Better::Lower => {
+                current > baseline * (1.0 + limit) && current - baseline >= MIN_LATENCY_DELTA_MS
+            }
+        };
+        Self {
+            name,
+            baseline,
+            current,
+            unit,
+            regressed,
+        }
+    }
+
+    fn change(&self) -> String {
+        if self.unit == "%" {
+            format!("{:+.2} pp", self.current - self.baseline)
+        } else if self.baseline == 0.0 {
+            "-".to_string()
+        } else {
+            format!("{:+.1} %", (self.current / self.baseline - 1.0) * 100.0)
+        }
+    }
+}
+
+/// Print how `current` differs from `baseline` and return the number of figures
+/// that got worse by more than `tolerance`. Per-URL latency is compared on the
+/// average of URLs requested in both runs.
+pub fn compare(baseline: &RunResults, current: &RunResults, tolerance: Tolerance) -> usize {
+    let latency = |name, pick: fn(&Latency) -> f64| {
+        Row::relative(
+            name,
+            pick(&baseline.latency),
+            pick(&current.latency),
+            "ms",
+            Better::Lower,
+            tolerance,
+        )
+    };
+    let rows = [
+        Row::relative(
+            "Throughput",
+            baseline.rps,
+            current.rps,
+            "/s",
+            Better::Higher,
+            tolerance,
+        ),
+        latency("Avg latency", |l| l.avg),
+        latency("p50 latency", |l| l.p50),
+        latency("p90 latency", |l| l.p90),
+        latency("p95 latency", |l| l.p95),
+        latency("p99 latency", |l| l.p99),
+        latency("Max latency", |l| l.max),
+        Row {
+            name: "Error rate",
+            baseline: baseline.error_rate,
+            current: current.error_rate,
+            unit: "%",
+            regressed: current.error_rate - baseline.error_rate > tolerance.error_rate,
+        },
+    ];
+
+    println!(
+        "Comparison with baseline ({}, {}):",
+        baseline.target, baseline.started_at
+    );
+    println!(
+        "  {:<14}{:>14}{:>14}{:>12}",
+        "", "Baseline", "Current", "Change"
+    );
+    for row in &rows {
+        println!(
+            "  {:<14}{:>14}{:>14}{:>12}{}",
+            row.name,
+            format!("{:.2} {}", row.baseline, row.unit),
+            format!("{:.2} {}", row.current, row.unit),
+            row.change(),
+            if row.regressed {
+                format!("  {}", "REGRESSED".red())
+            } else {
+                String::new()
+            }
+        );
+    }
+
+    // URLs in both runs whose average latency regressed, worst first
+    let mut urls: Vec<(&String, Row)> = current
+        .urls
+        .iter()
+        .filter_map(|(url, now)| {
+            let before = baseline.urls.get(url)?;
+            (before.requests > 0 && now.requests > 0).then(|| {
+                let row = Row::relative(
+                    "",
+                    before.latency.avg,
+                    now.latency.avg,
+                    "ms",
+                    Better::Lower,
+                    tolerance,
+                );
+                (url, row)
+            })
+        })
+        .filter(|(_, row)| row.regressed)
+        .collect();
+    urls.sort_by(|a, b| {
+        let ratio = |row: &Row| row.current / row.baseline.max(f64::EPSILON);
+        ratio(&b.1).partial_cmp(&ratio(&a.1)).unwrap()
+    });
+    let only_baseline = baseline
+        .urls
+        .keys()
+        .filter(|url| !current.urls.contains_key(*url))
+        .count();
+    let only_current = current
+        .urls
+        .keys()
+        .filter(|url| !baseline.urls.contains_key(*url))
+        .count();
+
+    if !urls.is_empty() {
+        println!(
+            "URLs with average latency up more than {}%: {}",
+            tolerance.percent,
+            urls.len()
+        );
+        for (url, row) in urls.iter().take(MAX_LISTED_URLS) {
+            println!(
+                "  {:>9.0} ms -> {:>7.0} ms {:>9}  {}",
+                row.baseline,
+                row.current,
+                row.change(),
+                url
+            );
+        }
+        if urls.len() > MAX_LISTED_URLS {
+            println!("  ... and {} more", urls.len() - MAX_LISTED_URLS);
+        }
+    }
+    if only_baseline > 0 || only_current > 0 {
+        println!(
+            "URLs only in the baseline: {}, only in this run: {}",
+            only_baseline, only_current
+        );
+    }
+    println!();
+
+    rows.iter().filter(|row| row.regressed).count() + urls.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::results::UrlResults;
+    use std::collections::BTreeMap;
+
+    const TOLERANCE: Tolerance = Tolerance {
+        percent: 10.0,
+        error_rate: 1.0,
+    };
+
+    fn latency(avg: f64) -> Latency {
+        Latency {
+            avg,
+            p50: avg,
+            p90: avg,
+            p95: avg,
+            p99: avg,
+            max: avg,
+        }
+    }
+
+    fn run(rps: f64, error_rate: f64, avg: f64, urls: &[(&str, f64)]) -> RunResults {
+        RunResults {
+            version: 1,
+            target: "https://example.com/".to_string(),
+            started_at: "2026-01-01T00:00:00Z".to_string(),
+            elapsed_secs: 10.0,
+            requests: 100,
+            successful: 100,
+            failed: 0,
+            availability: 100.0 - error_rate,
+            error_rate,
+            rps,
+            bytes: 0,
+            latency: latency(avg),
+            status_codes: BTreeMap::new(),
+            errors: BTreeMap::new(),
+            cache_hits: 0,
+            cache_misses: 0,
+            urls: urls
+                .iter()
+                .map(|&(url, avg)| {
+                    let results = UrlResults {
+                        is_main_url: true,
+                        requests: 10,
+                        failed: 0,
+                        bytes: 0,
+                        latency: latency(avg),
+                    };
+                    (url.to_string(), results)
+                })
+                .collect(),
+            interval_secs: 0.0,
+            intervals: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn same_run_has_no_regressions() {
+        let baseline = run(50.0, 0.5, 200.0, &[("/a", 200.0)]);
+        assert_eq!(compare(&baseline, &baseline, TOLERANCE), 0);
+    }
+
+    #[test]
+    fn changes_within_tolerance_pass() {
+        let baseline = run(50.0, 0.5, 200.0, &[("/a", 200.0)]);
+        let current = run(46.0, 1.4, 219.0, &[("/a", 219.0)]);
+        assert_eq!(compare(&baseline, &current, TOLERANCE), 0);
+    }
+
+    #[test]
+    fn counts_each_regressed_figure() {
+        let baseline = run(50.0, 0.5, 200.0, &[]);
+        // Throughput, the six latency figures and the error rate
+        let current = run(40.0, 2.0, 300.0, &[]);
+        assert_eq!(compare(&baseline, &current, TOLERANCE), 8);
+        // Faster and more reliable is never a regression
+        assert_eq!(compare(&current, &baseline, TOLERANCE), 0);
+    }
+
+    #[test]
+    fn ignores_small_latency_changes() {
+        // +50% but under MIN_LATENCY_DELTA_MS
+        let baseline = run(50.0, 0.0, 4.0, &[("/a", 4.0)]);
+        let current = run(50.0, 0.0, 6.0, &[("/a", 6.0)]);
+        assert_eq!(compare(&baseline, &current, TOLERANCE), 0);
+    }
+
+    #[test]
+    fn counts_regressed_urls_in_both_runs() {
+        let baseline = run(
+            50.0,
+            0.0,
+            200.0,
+            &[("/a", 100.0), ("/b", 100.0), ("/gone", 100.0)],
+        );
+        let current = run(
+            50.0,
+            0.0,
+            200.0,
+            &[("/a", 150.0), ("/b", 105.0), ("/new", 900.0)],
+        );
+        assert_eq!(compare(&baseline, &current, TOLERANCE), 1);
+    }
+}
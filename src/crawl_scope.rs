@@ -0,0 +1,109 @@
+use serde::Deserialize;
+use url::Url;
+
+/// Which hosts the link-following crawlers may leave the start host for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ScopeMode {
+    /// Only the exact host of the start URL
+    Host,
+    /// The start host and any of its subdomains
+    Subdomains,
+}
+
+/// Page, depth and scope limits shared by the follow-links and JS crawlers.
+#[derive(Clone, Debug)]
+pub struct CrawlLimits {
+    /// Maximum number of pages to discover; `None` means unlimited.
+    pub max_pages: Option<usize>,
+    /// Maximum link depth from the start URL (the start URL is depth 0).
+    pub max_depth: Option<usize>,
+    mode: ScopeMode,
+    start_host: String,
+    allowed_hosts: Vec<String>,
+    path_prefix: Option<String>,
+}
+
+impl CrawlLimits {
+    pub fn new(
+        start_url: &str,
+        max_pages: Option<usize>,
+        max_depth: Option<usize>,
+        mode: ScopeMode,
+        allowed_hosts: &[String],
+        path_prefix: Option<String>,
+    ) -> Self {
+        let start_host = Url::parse(start_url)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_lowercase()))
+            .unwrap_or_default();
+        Self {
+            max_pages,
+            max_depth,
+            mode,
+            start_host,
+            allowed_hosts: allowed_hosts.iter().map(|h| h.to_lowercase()).collect(),
+            path_prefix,
+        }
+    }
+
+    /// Whether a discovered link is inside the crawl scope (host rules and path prefix).
+    pub fn in_scope(&self, url: &str) -> bool {
+        let Ok(parsed) = Url::parse(url) else {
+            return false;
+        };
+        let Some(host) = parsed.host_str().map(|h| h.to_lowercase()) else {
+            return false;
+        };
+
+        let host_ok = host == self.start_host
+            || self.allowed_hosts.contains(&host)
+            || (self.mode == ScopeMode::Subdomains && self.is_subdomain(&host));
+        if !host_ok {
+            return false;
+        }
+
+        match self.path_prefix {
+            Some(ref prefix) => parsed.path().starts_with(prefix.as_str()),
+            None => true,
+        }
+    }
+
+    /// Whether a link found on a page at `parent_depth` may still be followed.
+    pub fn depth_allows(&self, parent_depth: usize) -> bool {
+        self.max_depth.is_none_or(|max| parent_depth < max)
+    }
+
+    /// Whether `pages` discovered pages have hit the page cap.
+    pub fn pages_reached(&self, pages: usize) -> bool {
+        self.max_pages.is_some_and(|max| pages >= max)
+    }
+
+    /// Subdomain of the start host, ignoring a leading `www.` on the start host so that
+    /// starting at www.example.com also covers shop.example.com.
+    fn is_subdomain(&self, host: &str) -> bool {
+        let base = self
+            .start_host
+            .strip_prefix("www.")
+            .unwrap_or(&self.start_host);
+        host == base || host.ends_with(&format!(".{}", base))
+    }
+
+    /// Print which limits stopped the crawl, if any.
+    pub fn report(&self, page_limit_hit: bool, depth_limited: usize) {
+        if page_limit_hit && let Some(max) = self.max_pages {
+            println!(
+                "Crawl stopped at the page limit ({} pages); raise --max-pages to discover more",
+                max
+            );
+        }
+        if depth_limited > 0
+            && let Some(max) = self.max_depth
+        {
+            println!(
+                "{} links beyond depth {} were not followed (--max-depth)",
+                depth_limited, max
+            );
+        }
+    }
+}
@@ -0,0 +1,305 @@
+use crate::RequestResult;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How often progress is written to disk while a crawl runs.
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Result of a page request, as recorded in the state file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Outcome {
+    /// HTTP status; absent for pages rendered in the browser by the JS crawler
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Outcome {
+    pub fn of(result: &RequestResult) -> Self {
+        Self {
+            status: (result.status_code != 0).then_some(result.status_code),
+            error: result
+                .error
+                .as_ref()
+                .map(|e| format!("{}: {}", e.category, e.message)),
+        }
+    }
+}
+
+/// The two resumable parts of a run: URL discovery (follow-links and JS crawls) and
+/// the `-w` crawl over the discovered URLs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Discovery,
+    Crawl,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Progress {
+    /// URL -> outcome of its page request
+    processed: HashMap<String, Outcome>,
+    /// Queued but not yet processed URLs, with their link depth
+    pending: HashMap<String, usize>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct StateFile {
+    start_url: String,
+    mode: String,
+    discovery: Progress,
+    /// Final URL list once discovery has finished
+    discovered: Option<Vec<String>>,
+    crawl: Progress,
+}
+
+impl StateFile {
+    fn progress(&mut self, phase: Phase) -> &mut Progress {
+        match phase {
+            Phase::Discovery => &mut self.discovery,
+            Phase::Crawl => &mut self.crawl,
+        }
+    }
+}
+
+/// Crawl progress checkpointed to a JSON file so an interrupted run can `--resume`.
+pub struct CrawlState {
+    path: PathBuf,
+    data: Mutex<StateFile>,
+    last_save: Mutex<Instant>,
+}
+
+impl CrawlState {
+    /// Open the state file for a crawl of `start_url` in `mode`. With `resume`, progress
+    /// saved by an earlier run of the same crawl is loaded; otherwise the run starts fresh
+    /// and overwrites the file on the first checkpoint.
+    pub fn open(path: &str, start_url: &str, mode: &str, resume: bool) -> Result<Self, String> {
+        let mut data = StateFile {
+            start_url: start_url.to_string(),
+            mode: mode.to_string(),
+            ..Default::default()
+        };
+
+        if resume {
+            match fs::read_to_string(path) {
+                Ok(content) => {
+                    let saved: StateFile = serde_json::from_str(&content)
+                        .map_err(|e| format!("Invalid state file {}: {}", path, e))?;
+                    if saved.start_url != start_url || saved.mode != mode {
+                        return Err(format!(
+                            "State file {} is for a {} crawl of {}, not a {} crawl of {}",
+                            path, saved.mode, saved.start_url, mode, start_url
+                        ));
+                    }
+                    println!(
+                        "Resuming from {}: {} pages discovered, {} queued, {} crawled",
+                        path,
+                        saved.discovery.processed.len(),
+                        saved.discovery.pending.len(),
+                        saved.crawl.processed.len()
+                    );
+                    data = saved;
+                }
+                Err(_) => println!("No state file at {}, starting a fresh crawl", path),
+            }
+        }
+
+        Ok(Self {
+            path: PathBuf::from(path),
+            data: Mutex::new(data),
+            last_save: Mutex::new(Instant::now()),
+        })
+    }
+
+    /// The URL list of a finished discovery, so a resumed run can skip it.
+    pub fn discovered(&self) -> Option<Vec<String>> {
+        self.data.lock().unwrap().discovered.clone()
+    }
+
+    pub fn set_discovered(&self, urls: &[String]) {
+        self.data.lock().unwrap().discovered = Some(urls.to_vec());
+        self.save();
+    }
+
+    /// URLs already processed in `phase`, and the queued ones (shallowest first).
+    pub fn resume_point(&self, phase: Phase) -> (HashSet<String>, Vec<(String, usize)>) {
+        let mut data = self.data.lock().unwrap();
+        let progress = data.progress(phase);
+        let processed = progress.processed.keys().cloned().collect();
+        let mut pending: Vec<(String, usize)> = progress
+            .pending
+            .iter()
+            .map(|(url, depth)| (url.clone(), *depth))
+            .collect();
+        pending.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        (processed, pending)
+    }
+
+    /// Remember a URL that was queued for processing.
+    pub fn queue(&self, phase: Phase, url: &str, depth: usize) {
+        self.data
+            .lock()
+            .unwrap()
+            .progress(phase)
+            .pending
+            .insert(url.to_string(), depth);
+    }
+
+    /// Mark a URL processed. Queue the links found on it first, so a checkpoint never
+    /// holds a processed page whose links are missing.
+    pub fn record(&self, phase: Phase, url: &str, outcome: Outcome) {
+        let mut data = self.data.lock().unwrap();
+        let progress = data.progress(phase);
+        progress.pending.remove(url);
+        progress.processed.insert(url.to_string(), outcome);
+    }
+
+    /// Save if the last save is older than the checkpoint interval.
+    pub fn checkpoint(&self) {
+        let due = self.last_save.lock().unwrap().elapsed() >= SAVE_INTERVAL;
+        if due {
+            self.save();
+        }
+    }
+
+    /// Write the state file (via a temporary file, so an interrupted write never
+    /// leaves a truncated state behind).
+    pub fn save(&self) {
+        let json = match serde_json::to_string(&*self.data.lock().unwrap()) {
+            Ok(json) => json,
+            Err(e) => {
+                eprintln!("Failed to serialize crawl state: {}", e);
+                return;
+            }
+        };
+        let tmp = self.path.with_extension("tmp");
+        if let Err(e) = fs::write(&tmp, json).and_then(|_| fs::rename(&tmp, &self.path)) {
+            eprintln!(
+                "Failed to write crawl state to {}: {}",
+                self.path.display(),
+                e
+            );
+        }
+        *self.last_save.lock().unwrap() = Instant::now();
+    }
+
+    /// The run finished: remove the state file so the next `--resume` starts fresh.
+    pub fn complete(&self) {
+        if fs::remove_file(&self.path).is_ok() {
+            println!("Run complete, removed state file {}", self.path.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: &str = "https://example.com/";
+
+    /// A state file path unique to the test, removed beforehand.
+    fn state_path(name: &str) -> String {
+        let path =
+            std::env::temp_dir().join(format!("warmer-state-{}-{}.json", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path.to_string_lossy().into_owned()
+    }
+
+    fn ok() -> Outcome {
+        Outcome {
+            status: Some(200),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn resumes_the_saved_queue_and_visited_set() {
+        let path = state_path("round-trip");
+        let state = CrawlState::open(&path, START, "follow-links", false).unwrap();
+        state.queue(Phase::Discovery, START, 0);
+        state.queue(Phase::Discovery, "https://example.com/b", 1);
+        state.queue(Phase::Discovery, "https://example.com/a", 1);
+        state.queue(Phase::Discovery, "https://example.com/deep", 2);
+        state.record(Phase::Discovery, START, ok());
+        state.queue(Phase::Crawl, "https://example.com/a", 0);
+        state.save();
+
+        let resumed = CrawlState::open(&path, START, "follow-links", true).unwrap();
+        let (visited, pending) = resumed.resume_point(Phase::Discovery);
+        assert_eq!(visited, HashSet::from([START.to_string()]));
+        // Shallowest first, then by URL
+        assert_eq!(
+            pending,
+            [
+                ("https://example.com/a".to_string(), 1),
+                ("https://example.com/b".to_string(), 1),
+                ("https://example.com/deep".to_string(), 2),
+            ]
+        );
+        let (crawled, queued) = resumed.resume_point(Phase::Crawl);
+        assert!(crawled.is_empty());
+        assert_eq!(queued, [("https://example.com/a".to_string(), 0)]);
+        assert_eq!(resumed.discovered(), None);
+
+        resumed.set_discovered(&[START.to_string()]);
+        let again = CrawlState::open(&path, START, "follow-links", true).unwrap();
+        assert_eq!(again.discovered(), Some(vec![START.to_string()]));
+        again.complete();
+    }
+
+    #[test]
+    fn without_resume_the_saved_progress_is_ignored() {
+        let path = state_path("fresh");
+        let state = CrawlState::open(&path, START, "js", false).unwrap();
+        state.record(Phase::Discovery, START, ok());
+        state.save();
+
+        let fresh = CrawlState::open(&path, START, "js", false).unwrap();
+        let (visited, pending) = fresh.resume_point(Phase::Discovery);
+        assert!(visited.is_empty() && pending.is_empty());
+        fresh.complete();
+    }
+
+    #[test]
+    fn complete_removes_the_file() {
+        let path = state_path("complete");
+        let state = CrawlState::open(&path, START, "sitemap", false).unwrap();
+        state.save();
+        assert!(fs::metadata(&path).is_ok());
+        state.complete();
+        assert!(fs::metadata(&path).is_err());
+        // Nothing left to remove is fine
+        state.complete();
+    }
+
+    #[test]
+    fn missing_file_starts_fresh() {
+        let path = state_path("missing");
+        let state = CrawlState::open(&path, START, "follow-links", true).unwrap();
+        assert!(state.resume_point(Phase::Discovery).1.is_empty());
+    }
+
+    #[test]
+    fn corrupt_or_mismatched_files_are_errors() {
+        let path = state_path("corrupt");
+        fs::write(&path, "{\"start_url\": ").unwrap();
+        let error = CrawlState::open(&path, START, "follow-links", true)
+            .err()
+            .unwrap();
+        assert!(error.starts_with("Invalid state file"), "{}", error);
+
+        let state = CrawlState::open(&path, START, "follow-links", false).unwrap();
+        state.save();
+        let error = CrawlState::open(&path, "https://other.example/", "follow-links", true)
+            .err()
+            .unwrap();
+        assert!(error.contains("not a follow-links crawl of"), "{}", error);
+        let error = CrawlState::open(&path, START, "js", true).err().unwrap();
+        assert!(error.contains("not a js crawl"), "{}", error);
+        state.complete();
+    }
+}
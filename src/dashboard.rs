@@ -0,0 +1,241 @@
+use crate::histogram::Histogram;
+use crate::{ErrorCategory, Stats};
+use colored::Colorize;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+
+/// True while the dashboard owns the terminal; per-request lines are not printed then.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+/// Virtual users (load mode) or crawl workers currently running.
+static USERS: AtomicUsize = AtomicUsize::new(0);
+/// Crawl progress: pages finished and pages to crawl.
+static DONE: AtomicUsize = AtomicUsize::new(0);
+static TOTAL: AtomicUsize = AtomicUsize::new(0);
+
+const REFRESH: Duration = Duration::from_secs(1);
+
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+/// Counts a running user or crawl worker for as long as it is held.
+pub struct UserGuard;
+
+impl UserGuard {
+    pub fn new() -> Self {
+        USERS.fetch_add(1, Ordering::Relaxed);
+        Self
+    }
+}
+
+impl Drop for UserGuard {
+    fn drop(&mut self) {
+        USERS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Set the number of pages a crawl will process, for the progress line.
+pub fn set_total(total: usize) {
+    TOTAL.store(total, Ordering::Relaxed);
+}
+
+pub fn page_done() {
+    DONE.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Crawl progress as (pages finished, pages to crawl).
+pub fn progress() -> (usize, usize) {
+    (DONE.load(Ordering::Relaxed), TOTAL.load(Ordering::Relaxed))
+}
+
+pub fn active_users() -> usize {
+    USERS.load(Ordering::Relaxed)
+}
+
+/// Full-screen live view of `Stats`, redrawn every second on the terminal's
+/// alternate screen.
+pub struct Dashboard {
+    task: JoinHandle<()>,
+}
+
+impl Dashboard {
+    pub fn start(stats: Arc<Mutex<Stats>>, title: String) -> Self {
+        ACTIVE.store(true, Ordering::Relaxed);
+        // Switch to the alternate screen and hide the cursor
+        print!("\x1b[?1049h\x1b[?25l");
+        let _ = std::io::stdout().flush();
+
+        let task = tokio::spawn(async move {
+            let mut last = (Instant::now(), 0);
+            loop {
+                let frame = render(&stats, &title, &mut last);
+                print!("\x1b[H\x1b[2J{}", frame);
+                let _ = std::io::stdout().flush();
+                sleep(REFRESH).await;
+            }
+        });
+        Self { task }
+    }
+
+    pub fn stop(self) {
+        self.task.abort();
+        restore_terminal();
+    }
+}
+
+/// Leave the alternate screen if the dashboard is showing. Safe to call from the
+/// Ctrl-C handler before a forced exit.
+pub fn restore_terminal() {
+    if ACTIVE.swap(false, Ordering::Relaxed) {
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = std::io::stdout().flush();
+    }
+}
+
+/// Numbers copied out of `Stats` so the lock is not held while formatting.
+struct Snapshot {
+    transactions: usize,
+    successful: usize,
+    failed: usize,
+    elapsed: f64,
+    latency: Histogram,
+    status_codes: HashMap<u16, usize>,
+    errors: HashMap<ErrorCategory, usize>,
+    cache_hits: usize,
+    cache_misses: usize,
+}
+
+fn render(stats: &Mutex<Stats>, title: &str, last: &mut (Instant, usize)) -> String {
+    let snap = {
+        let stats = stats.lock().unwrap();
+        Snapshot {
+            transactions: stats.transactions,
+            successful: stats.successful_transactions,
+            failed: stats.failed_transactions,
+            elapsed: stats.elapsed_time(),
+            latency: stats.latency.clone(),
+            status_codes: stats.status_codes.clone(),
+            errors: stats.errors.clone(),
+            cache_hits: stats.cache_hits,
+            cache_misses: stats.cache_misses,
+        }
+    };
+
+    // Requests/sec over the last refresh, and over the whole run
+    let now = Instant::now();
+    let interval = now.duration_since(last.0).as_secs_f64();
+    let current_rate = if interval > 0.0 {
+        snap.transactions.saturating_sub(last.1) as f64 / interval
+    } else {
+        0.0
+    };
+    *last = (now, snap.transactions);
+    let average_rate = if snap.elapsed > 0.0 {
+        snap.transactions as f64 / snap.elapsed
+    } else {
+        0.0
+    };
+
+    let mut out = String::new();
+    let secs = snap.elapsed as u64;
+    let _ = writeln!(
+        out,
+        "{}  {}    elapsed {:02}:{:02}:{:02}",
+        "WARMER".bold(),
+        title.bright_blue(),
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    );
+    out.push('\n');
+    let _ = writeln!(
+        out,
+        "Requests:       {:>10}    now {:>8.1}/s    avg {:>8.1}/s",
+        snap.transactions, current_rate, average_rate
+    );
+    let availability = if snap.transactions > 0 {
+        snap.successful as f64 / snap.transactions as f64 * 100.0
+    } else {
+        0.0
+    };
+    let _ = writeln!(
+        out,
+        "Successful:     {:>10}    failed {:>8}    availability {:.2} %",
+        snap.successful, snap.failed, availability
+    );
+    let _ = writeln!(out, "Active users:   {:>10}", active_users());
+
+    let (done, total) = progress();
+    if total > 0 {
+        let done = done.min(total);
+        let _ = writeln!(
+            out,
+            "Progress:       {}    {} / {} pages ({:.1} %)",
+            progress_bar(done, total, 30),
+            done,
+            total,
+            done as f64 / total as f64 * 100.0
+        );
+    }
+
+    let latency = |p: f64| snap.latency.percentile(p).unwrap_or(0.0);
+    if let Some(max) = snap.latency.max() {
+        let _ = writeln!(
+            out,
+            "Latency (ms):   p50 {:.1}    p90 {:.1}    p95 {:.1}    p99 {:.1}    max {:.1}",
+            latency(50.0),
+            latency(90.0),
+            latency(95.0),
+            latency(99.0),
+            max
+        );
+    }
+
+    let cached = snap.cache_hits + snap.cache_misses;
+    if cached > 0 {
+        let _ = writeln!(
+            out,
+            "Cache:          hit ratio {:.1} %    {} hits    {} misses",
+            snap.cache_hits as f64 / cached as f64 * 100.0,
+            snap.cache_hits,
+            snap.cache_misses
+        );
+    } else {
+        let _ = writeln!(out, "Cache:          no cache status headers seen");
+    }
+
+    let mut codes: Vec<(u16, usize)> = snap.status_codes.into_iter().collect();
+    codes.sort();
+    let codes: Vec<String> = codes
+        .iter()
+        .map(|(code, count)| match code {
+            0 => format!("error: {}", count),
+            _ => format!("{}: {}", code, count),
+        })
+        .collect();
+    let _ = writeln!(out, "Status codes:   {}", codes.join("    "));
+
+    if !snap.errors.is_empty() {
+        let mut errors: Vec<(ErrorCategory, usize)> = snap.errors.into_iter().collect();
+        errors.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        out.push_str("Errors:\n");
+        for (category, count) in errors {
+            let _ = writeln!(out, "  {:<22}{:8}", format!("{}:", category), count);
+        }
+    }
+
+    out.push('\n');
+    out.push_str("Press Ctrl-C to stop\n");
+    out
+}
+
+fn progress_bar(done: usize, total: usize, width: usize) -> String {
+    let filled = done * width / total.max(1);
+    format!("[{}{}]", "#".repeat(filled), " ".repeat(width - filled))
+}
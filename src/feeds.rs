@@ -0,0 +1,260 @@
+use crate::asset_policy::AssetType;
+use crate::url_norm;
+use scraper::{Html, Selector};
+use url::Url;
+use xml::reader::{EventReader, XmlEvent};
+
+/// One RSS `<item>` or Atom `<entry>`.
+#[derive(Debug, Default)]
+pub struct FeedItem {
+    /// Page URL of the item, resolved against the feed URL
+    pub link: Option<String>,
+    /// Enclosures and Media RSS content/thumbnails, with their asset type
+    pub enclosures: Vec<(String, AssetType)>,
+}
+
+/// Parse an RSS 2.0 or Atom feed into its items.
+///
+/// Reads `<link>` (RSS text or Atom `href` with no rel or `rel="alternate"`),
+/// RSS `<enclosure url>`, Atom `<link rel="enclosure">` and Media RSS
+/// `<media:content url>` / `<media:thumbnail url>`. Malformed XML ends the scan and
+/// keeps whatever was collected so far.
+pub fn parse(content: &str, feed_url: &str) -> Vec<FeedItem> {
+    let Ok(base) = Url::parse(feed_url) else {
+        return Vec::new();
+    };
+    let mut items = Vec::new();
+    let mut current: Option<FeedItem> = None;
+    let mut in_link = false;
+    let mut text = String::new();
+
+    for event in EventReader::from_str(content) {
+        match event {
+            Ok(XmlEvent::StartElement {
+                name, attributes, ..
+            }) => {
+                let attr = |key: &str| {
+                    attributes
+                        .iter()
+                        .find(|a| a.name.local_name == key)
+                        .map(|a| a.value.as_str())
+                };
+                let local = name.local_name.as_str();
+                if local == "item" || local == "entry" {
+                    current = Some(FeedItem::default());
+                    continue;
+                }
+                let Some(item) = current.as_mut() else {
+                    continue;
+                };
+                match (name.prefix.as_deref(), local) {
+                    (None, "link") => match attr("href") {
+                        // Atom: the page is the alternate link, enclosures are assets
+                        Some(href) => match attr("rel").unwrap_or("alternate") {
+                            "alternate" if item.link.is_none() => {
+                                item.link = url_norm::resolve(href, &base);
+                            }
+                            "enclosure" => push_enclosure(item, href, attr("type"), &base),
+                            _ => {}
+                        },
+                        // RSS: the link is element text
+                        None => {
+                            in_link = true;
+                            text.clear();
+                        }
+                    },
+                    (None, "enclosure") | (Some(_), "content") => {
+                        if let Some(url) = attr("url") {
+                            push_enclosure(item, url, attr("type"), &base);
+                        }
+                    }
+                    (Some(_), "thumbnail") => {
+                        if let Some(url) = attr("url") {
+                            push_enclosure(item, url, Some("image/*"), &base);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Ok(XmlEvent::Characters(chunk)) | Ok(XmlEvent::CData(chunk)) => {
+                if in_link {
+                    text.push_str(&chunk);
+                }
+            }
+            Ok(XmlEvent::EndElement { name }) => {
+                let local = name.local_name.as_str();
+                if in_link && local == "link" {
+                    in_link = false;
+                    if let Some(item) = current.as_mut()
+                        && item.link.is_none()
+                    {
+                        item.link = url_norm::resolve(&text, &base);
+                    }
+                } else if (local == "item" || local == "entry")
+                    && let Some(item) = current.take()
+                {
+                    items.push(item);
+                }
+            }
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+
+    items
+}
+
+fn push_enclosure(item: &mut FeedItem, url: &str, mime: Option<&str>, base: &Url) {
+    let Some(url) = url_norm::resolve(url, base) else {
+        return;
+    };
+    let kind = match mime.and_then(|m| m.split('/').next()) {
+        Some("image") => AssetType::Image,
+        Some("video") | Some("audio") => AssetType::Media,
+        _ => AssetType::from_url(&url).unwrap_or(AssetType::Document),
+    };
+    item.enclosures.push((url, kind));
+}
+
+/// Feed URLs advertised by a page through
+/// `<link rel="alternate" type="application/rss+xml|application/atom+xml">`.
+pub fn discover(html_content: &str, page_url: &str) -> Vec<String> {
+    let html = Html::parse_document(html_content);
+    let Some(base) = url_norm::document_base(&html, page_url) else {
+        return Vec::new();
+    };
+    let Ok(selector) = Selector::parse("link[rel][type][href]") else {
+        return Vec::new();
+    };
+    html.select(&selector)
+        .filter(|link| {
+            let link = link.value();
+            let rel = link.attr("rel").unwrap_or_default().to_lowercase();
+            let mime = link.attr("type").unwrap_or_default().to_lowercase();
+            rel.split_whitespace().any(|t| t == "alternate")
+                && (mime == "application/rss+xml" || mime == "application/atom+xml")
+        })
+        .filter_map(|link| link.value().attr("href"))
+        .filter_map(|href| url_norm::resolve(href, &base))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEED_URL: &str = "https://example.com/blog/feed.xml";
+
+    #[test]
+    fn reads_rss_link_text() {
+        let rss = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel>
+  <link>https://example.com/blog/</link>
+  <item><title>First</title><link>https://example.com/blog/first</link></item>
+  <item><link><![CDATA[second]]></link></item>
+</channel></rss>"#;
+        let items = parse(rss, FEED_URL);
+        let links: Vec<_> = items.iter().map(|i| i.link.as_deref()).collect();
+        // The channel link is not an item
+        assert_eq!(
+            links,
+            [
+                Some("https://example.com/blog/first"),
+                Some("https://example.com/blog/second")
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_atom_alternate_and_enclosure_links() {
+        let atom = r#"<?xml version="1.0"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <link rel="self" href="/blog/feed.xml"/>
+  <entry>
+    <link rel="edit" href="/api/posts/1"/>
+    <link rel="alternate" href="/blog/atom-post"/>
+    <link rel="enclosure" type="audio/mpeg" href="/media/episode.mp3"/>
+  </entry>
+  <entry><link href="https://example.com/blog/no-rel"/></entry>
+</feed>"#;
+        let items = parse(atom, FEED_URL);
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items[0].link.as_deref(),
+            Some("https://example.com/blog/atom-post")
+        );
+        assert_eq!(
+            items[0].enclosures,
+            [(
+                "https://example.com/media/episode.mp3".to_string(),
+                AssetType::Media
+            )]
+        );
+        assert_eq!(
+            items[1].link.as_deref(),
+            Some("https://example.com/blog/no-rel")
+        );
+    }
+
+    #[test]
+    fn reads_enclosures_and_media_rss() {
+        let rss = r#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:media="http://search.yahoo.com/mrss/"
+     xmlns:content="http://purl.org/rss/1.0/modules/content/">
+<channel><item>
+  <link>https://example.com/blog/post</link>
+  <enclosure url="https://cdn.example.net/post.pdf" length="1" type="application/pdf"/>
+  <media:content url="video/clip.mp4" type="video/mp4"/>
+  <media:thumbnail url="https://cdn.example.net/thumb"/>
+  <content:encoded><![CDATA[<img src="https://example.com/inline.png"><link>/bogus</link>]]></content:encoded>
+</item></channel></rss>"#;
+        let items = parse(rss, FEED_URL);
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            items[0].link.as_deref(),
+            Some("https://example.com/blog/post")
+        );
+        assert_eq!(
+            items[0].enclosures,
+            [
+                (
+                    "https://cdn.example.net/post.pdf".to_string(),
+                    AssetType::Document
+                ),
+                (
+                    "https://example.com/blog/video/clip.mp4".to_string(),
+                    AssetType::Media
+                ),
+                (
+                    "https://cdn.example.net/thumb".to_string(),
+                    AssetType::Image
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_items_read_before_malformed_xml() {
+        let rss = "<rss><channel><item><link>/a</link></item><item><link>/b</oops>";
+        let items = parse(rss, FEED_URL);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].link.as_deref(), Some("https://example.com/a"));
+    }
+
+    #[test]
+    fn discovers_advertised_feeds() {
+        let html = r#"<html><head>
+  <link rel="alternate" type="application/rss+xml" href="feed.xml">
+  <link rel="Alternate" type="application/atom+xml" href="https://example.com/atom.xml">
+  <link rel="alternate" type="text/html" hreflang="de" href="/de/">
+  <link rel="stylesheet" type="text/css" href="/style.css">
+</head></html>"#;
+        assert_eq!(
+            discover(html, "https://example.com/blog/post"),
+            [
+                "https://example.com/blog/feed.xml",
+                "https://example.com/atom.xml"
+            ]
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+/// Upper bounds of the latency bins in ms: 1.0, 1.1 ... 9.9 times each power of ten
+/// from 0.01 ms to 99 s. Estimates stay within a few percent at any latency, and round
+/// numbers such as Prometheus bucket bounds fall on bin edges.
+fn bounds() -> &'static [f64] {
+    static BOUNDS: OnceLock<Vec<f64>> = OnceLock::new();
+    BOUNDS.get_or_init(|| {
+        (-3..4)
+            .flat_map(|exp: i32| {
+                // m / 10^k rounds to the same value as the literal, e.g. 0.3; m * 0.1^k doesn't
+                (10..100).map(move |m| {
+                    if exp < 0 {
+                        m as f64 / 10f64.powi(-exp)
+                    } else {
+                        m as f64 * 10f64.powi(exp)
+                    }
+                })
+            })
+            .collect()
+    })
+}
+
+/// Latency distribution of a run, a URL or a slice of the run. Count, sum, min and max
+/// are exact; percentiles are estimated from the bins. Only bins that were hit are
+/// stored, so a histogram per URL stays small.
+#[derive(Clone, Debug, Default)]
+pub struct Histogram {
+    /// Bin index -> responses; the bin past the last bound holds slower responses
+    counts: BTreeMap<usize, u64>,
+    count: u64,
+    sum: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Histogram {
+    pub fn record(&mut self, ms: f64) {
+        let bin = bounds().partition_point(|&bound| bound < ms);
+        *self.counts.entry(bin).or_insert(0) += 1;
+        self.min = if self.count == 0 {
+            ms
+        } else {
+            self.min.min(ms)
+        };
+        self.max = self.max.max(ms);
+        self.count += 1;
+        self.sum += ms;
+    }
+
+    pub fn merge(&mut self, other: &Histogram) {
+        if other.count == 0 {
+            return;
+        }
+        for (&bin, &count) in &other.counts {
+            *self.counts.entry(bin).or_insert(0) += count;
+        }
+        self.min = if self.count == 0 {
+            other.min
+        } else {
+            self.min.min(other.min)
+        };
+        self.max = self.max.max(other.max);
+        self.count += other.count;
+        self.sum += other.sum;
+    }
+
+    /// Responses recorded since `earlier`, an older copy of this histogram. The
+    /// fastest and slowest of them are estimated from their bins.
+    pub fn since(&self, earlier: &Histogram) -> Histogram {
+        let counts: BTreeMap<usize, u64> = self
+            .counts
+            .iter()
+            .map(|(&bin, &count)| {
+                let before = earlier.counts.get(&bin).copied().unwrap_or(0);
+                (bin, count.saturating_sub(before))
+            })
+            .filter(|&(_, count)| count > 0)
+            .collect();
+        let (Some(&first), Some(&last)) = (counts.keys().next(), counts.keys().next_back()) else {
+            return Histogram::default();
+        };
+        Histogram {
+            min: lower_bound(first).max(self.min),
+            max: bounds().get(last).map_or(self.max, |&b| b.min(self.max)),
+            counts,
+            count: self.count.saturating_sub(earlier.count),
+            sum: (self.sum - earlier.sum).max(0.0),
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Sum of all recorded latencies in ms.
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    pub fn avg(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.min)
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.max)
+    }
+
+    /// Latency below which `p` percent of responses fell, interpolated within the bin
+    /// and never outside the fastest and slowest response seen.
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = (p / 100.0 * self.count as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (&bin, &count) in &self.counts {
+            if seen + count >= rank {
+                let lower = lower_bound(bin);
+                let upper = bounds().get(bin).copied().unwrap_or(self.max);
+                let within = (rank - seen) as f64 / count as f64;
+                return Some((lower + (upper - lower) * within).clamp(self.min, self.max));
+            }
+            seen += count;
+        }
+        Some(self.max)
+    }
+
+    /// Responses that took at most `ms`, which should be a bin edge such as 2.5 or 300.
+    pub fn count_at_most(&self, ms: f64) -> u64 {
+        // Tolerate a bound that was computed rather than written out, like 0.1 * 3
+        let bins = bounds().partition_point(|&bound| bound <= ms * (1.0 + 1e-9));
+        self.counts.range(..bins).map(|(_, &count)| count).sum()
+    }
+}
+
+fn lower_bound(bin: usize) -> f64 {
+    if bin == 0 { 0.0 } else { bounds()[bin - 1] }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn of(values: &[f64]) -> Histogram {
+        let mut histogram = Histogram::default();
+        for &value in values {
+            histogram.record(value);
+        }
+        histogram
+    }
+
+    #[test]
+    fn empty_histogram_has_no_figures() {
+        let histogram = Histogram::default();
+        assert_eq!(histogram.avg(), None);
+        assert_eq!(histogram.max(), None);
+        assert_eq!(histogram.percentile(50.0), None);
+    }
+
+    #[test]
+    fn count_sum_min_and_max_are_exact() {
+        let histogram = of(&[12.0, 3.5, 480.0, 40.0]);
+        assert_eq!(histogram.count(), 4);
+        assert_eq!(histogram.sum(), 535.5);
+        assert_eq!(histogram.avg(), Some(133.875));
+        assert_eq!(histogram.min(), Some(3.5));
+        assert_eq!(histogram.max(), Some(480.0));
+    }
+
+    #[test]
+    fn percentiles_are_within_a_few_percent() {
+        let values: Vec<f64> = (1..=1000).map(|i| i as f64).collect();
+        let histogram = of(&values);
+        for (p, exact) in [(50.0, 500.0), (90.0, 900.0), (95.0, 950.0), (99.0, 990.0)] {
+            let estimate = histogram.percentile(p).unwrap();
+            assert!(
+                (estimate - exact).abs() / exact < 0.05,
+                "p{} = {}, expected about {}",
+                p,
+                estimate,
+                exact
+            );
+        }
+        assert_eq!(histogram.percentile(100.0), Some(1000.0));
+    }
+
+    #[test]
+    fn percentiles_stay_between_min_and_max() {
+        let histogram = of(&[42.0]);
+        assert_eq!(histogram.percentile(1.0), Some(42.0));
+        assert_eq!(histogram.percentile(99.0), Some(42.0));
+        let slow = of(&[250_000.0, 300_000.0]);
+        assert_eq!(slow.percentile(100.0), Some(300_000.0));
+    }
+
+    #[test]
+    fn merge_adds_both_sides() {
+        let mut histogram = of(&[5.0, 50.0]);
+        histogram.merge(&of(&[1.0, 500.0]));
+        histogram.merge(&Histogram::default());
+        assert_eq!(histogram.count(), 4);
+        assert_eq!(histogram.min(), Some(1.0));
+        assert_eq!(histogram.max(), Some(500.0));
+    }
+
+    #[test]
+    fn since_keeps_only_newer_responses() {
+        let mut histogram = of(&[10.0, 20.0]);
+        let earlier = histogram.clone();
+        histogram.record(300.0);
+        histogram.record(310.0);
+        let recent = histogram.since(&earlier);
+        assert_eq!(recent.count(), 2);
+        assert_eq!(recent.avg(), Some(305.0));
+        assert!(recent.min().unwrap() >= 290.0);
+        assert!(recent.max().unwrap() <= 310.0);
+        assert_eq!(histogram.since(&histogram).count(), 0);
+    }
+
+    #[test]
+    fn count_at_most_uses_bin_edges() {
+        let histogram = of(&[4.0, 5.0, 5.01, 250.0, 2500.0]);
+        assert_eq!(histogram.count_at_most(5.0), 2);
+        assert_eq!(histogram.count_at_most(0.005 * 1000.0), 2);
+        assert_eq!(histogram.count_at_most(250.0), 4);
+        assert_eq!(histogram.count_at_most(0.3), 0);
+        assert_eq!(histogram.count_at_most(30_000.0), 5);
+    }
+}
@@ -0,0 +1,46 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use url::Url;
+
+/// Politeness limit applied per host during a `-w` crawl: at most `max_concurrent`
+/// requests in flight. Request rates per host are capped by `RateLimits` in every mode.
+pub struct HostLimits {
+    max_concurrent: Option<usize>,
+    hosts: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+/// Held while a request to a capped host is in flight.
+pub struct HostPermit {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl HostLimits {
+    pub fn new(max_concurrent: Option<usize>) -> Self {
+        Self {
+            max_concurrent: max_concurrent.filter(|&n| n > 0),
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Wait until a request to `url`'s host may start under the concurrency cap.
+    pub async fn acquire(&self, url: &str) -> HostPermit {
+        let host = Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_lowercase()));
+        let (Some(max_concurrent), Some(host)) = (self.max_concurrent, host) else {
+            return HostPermit { _permit: None };
+        };
+
+        let permits = self
+            .hosts
+            .lock()
+            .unwrap()
+            .entry(host)
+            .or_insert_with(|| Arc::new(Semaphore::new(max_concurrent)))
+            .clone();
+        HostPermit {
+            _permit: permits.acquire_owned().await.ok(),
+        }
+    }
+}
@@ -0,0 +1,505 @@
+use crate::thresholds::Verdict;
+use crate::timeseries::TimeSeries;
+use crate::{ErrorCategory, Stats, UrlStats};
+use chrono::SecondsFormat;
+use std::fmt::Write as _;
+
+/// Most points a chart draws; longer runs merge neighbouring buckets.
+const CHART_POINTS: usize = 300;
+/// Rows in the slowest-URL table.
+const SLOWEST: usize = 20;
+
+const CHART_WIDTH: f64 = 900.0;
+const CHART_HEIGHT: f64 = 240.0;
+const MARGIN_LEFT: f64 = 60.0;
+const MARGIN_BOTTOM: f64 = 30.0;
+
+const STYLE: &str = "
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; margin: 2em auto; max-width: 1100px; color: #222; padding: 0 1em; }
+h1 { font-size: 1.6em; margin-bottom: 0.2em; }
+h2 { font-size: 1.2em; margin-top: 2em; border-bottom: 1px solid #ddd; padding-bottom: 0.3em; }
+.subtitle { color: #666; margin-top: 0; }
+.cards { display: grid; grid-template-columns: repeat(auto-fill, minmax(160px, 1fr)); gap: 0.8em; }
+.card { background: #f6f8fa; border-radius: 6px; padding: 0.8em; }
+.card .value { font-size: 1.4em; font-weight: 600; }
+.card .label { color: #666; font-size: 0.85em; }
+table { border-collapse: collapse; width: 100%; font-size: 0.9em; }
+th, td { text-align: left; padding: 0.3em 0.6em; border-bottom: 1px solid #eee; vertical-align: top; }
+th { background: #f6f8fa; }
+td.num, th.num { text-align: right; font-variant-numeric: tabular-nums; }
+td.url { word-break: break-all; }
+.bad { color: #c0392b; }
+.legend span { display: inline-block; margin-right: 1.2em; font-size: 0.85em; }
+.legend i { display: inline-block; width: 12px; height: 12px; margin-right: 0.3em; vertical-align: -1px; }
+svg text { font-size: 11px; fill: #666; }
+";
+
+/// Render a single, self-contained HTML page (inline CSS and SVG, no scripts or
+/// external assets) describing the run.
+pub fn render(
+    stats: &Stats,
+    title: &str,
+    config: &[(&str, String)],
+    verdicts: &[Verdict],
+) -> String {
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>warmer report: {}</title>\n<style>{}</style>\n</head>\n<body>\n",
+        escape(title),
+        STYLE
+    );
+    let _ = writeln!(out, "<h1>warmer report</h1>");
+    let _ = writeln!(
+        out,
+        "<p class=\"subtitle\">{} &middot; started {} &middot; {:.1} s</p>",
+        escape(title),
+        stats.started_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+        stats.elapsed_time()
+    );
+
+    summary(&mut out, stats);
+    thresholds(&mut out, verdicts);
+
+    let series = stats.timeline.downsample(CHART_POINTS);
+    let _ = writeln!(
+        out,
+        "<p class=\"subtitle\">Charts show one point per {} s.</p>",
+        series.width().as_secs_f64()
+    );
+    out.push_str("<h2>Latency over time</h2>\n");
+    latency_chart(&mut out, &series);
+    out.push_str("<h2>Throughput over time</h2>\n");
+    throughput_chart(&mut out, &series);
+    out.push_str("<h2>Error rate over time</h2>\n");
+    error_rate_chart(&mut out, &series);
+
+    status_codes(&mut out, stats);
+    errors(&mut out, stats);
+    cache(&mut out, stats);
+    slowest_urls(&mut out, stats);
+    url_table(&mut out, stats);
+
+    out.push_str("<h2>Run configuration</h2>\n<table>\n");
+    for (name, value) in config {
+        let _ = writeln!(
+            out,
+            "<tr><th>{}</th><td>{}</td></tr>",
+            escape(name),
+            escape(value)
+        );
+    }
+    out.push_str("</table>\n");
+
+    let _ = writeln!(
+        out,
+        "<p class=\"subtitle\">Generated by warmer {}</p>\n</body>\n</html>",
+        env!("CARGO_PKG_VERSION")
+    );
+    out
+}
+
+fn summary(out: &mut String, stats: &Stats) {
+    let latency = |p: f64| {
+        stats
+            .latency
+            .percentile(p)
+            .map_or("-".to_string(), |ms| format!("{:.0} ms", ms))
+    };
+
+    let cards = [
+        ("Transactions", stats.transactions.to_string()),
+        ("Availability", format!("{:.2} %", stats.availability())),
+        ("Failed", stats.failed_transactions.to_string()),
+        (
+            "Transaction rate",
+            format!("{:.1} /s", stats.transaction_rate()),
+        ),
+        ("Throughput", format!("{:.2} MB/s", stats.throughput())),
+        (
+            "Data transferred",
+            format!(
+                "{:.2} MB",
+                stats.data_transferred as f64 / (1024.0 * 1024.0)
+            ),
+        ),
+        (
+            "Average latency",
+            format!("{:.0} ms", stats.avg_response_time()),
+        ),
+        ("p50 latency", latency(50.0)),
+        ("p95 latency", latency(95.0)),
+        ("p99 latency", latency(99.0)),
+        (
+            "Longest",
+            stats
+                .latency
+                .max()
+                .map_or("-".to_string(), |t| format!("{:.0} ms", t)),
+        ),
+        ("Cache hit ratio", cache_ratio(stats)),
+    ];
+    out.push_str("<h2>Summary</h2>\n<div class=\"cards\">\n");
+    for (label, value) in cards {
+        let _ = writeln!(
+            out,
+            "<div class=\"card\"><div class=\"value\">{}</div><div class=\"label\">{}</div></div>",
+            escape(&value),
+            label
+        );
+    }
+    out.push_str("</div>\n");
+}
+
+fn thresholds(out: &mut String, verdicts: &[Verdict]) {
+    if verdicts.is_empty() {
+        return;
+    }
+    out.push_str(
+        "<h2>Thresholds</h2>\n<table>\n<tr><th>Result</th><th>Threshold</th><th>Actual</th></tr>\n",
+    );
+    for verdict in verdicts {
+        let _ = writeln!(
+            out,
+            "<tr><td{}>{}</td><td>{}</td><td>{}</td></tr>",
+            if verdict.passed { "" } else { " class=\"bad\"" },
+            if verdict.passed { "PASS" } else { "FAIL" },
+            escape(verdict.expression()),
+            escape(&verdict.actual())
+        );
+    }
+    out.push_str("</table>\n");
+}
+
+fn cache_ratio(stats: &Stats) -> String {
+    let cached = stats.cache_hits + stats.cache_misses;
+    if cached == 0 {
+        "-".to_string()
+    } else {
+        format!("{:.1} %", stats.cache_hits as f64 / cached as f64 * 100.0)
+    }
+}
+
+/// p50, p95 and max latency per bucket.
+fn latency_chart(out: &mut String, series: &TimeSeries) {
+    let lines: Vec<(&str, &str, Vec<Option<f64>>)> = vec![
+        (
+            "p50",
+            "#2e86de",
+            series
+                .buckets()
+                .iter()
+                .map(|b| b.latency.percentile(50.0))
+                .collect(),
+        ),
+        (
+            "p95",
+            "#e67e22",
+            series
+                .buckets()
+                .iter()
+                .map(|b| b.latency.percentile(95.0))
+                .collect(),
+        ),
+        (
+            "max",
+            "#c0392b",
+            series.buckets().iter().map(|b| b.latency.max()).collect(),
+        ),
+    ];
+    line_chart(out, series, "ms", &lines, "No requests recorded.");
+}
+
+/// Percent of requests that failed per bucket.
+fn error_rate_chart(out: &mut String, series: &TimeSeries) {
+    let lines = vec![(
+        "error rate",
+        "#c0392b",
+        series.buckets().iter().map(|b| b.error_rate()).collect(),
+    )];
+    line_chart(out, series, "%", &lines, "No failed requests.");
+}
+
+/// One polyline per (name, color, value per bucket), scaled to the largest value.
+fn line_chart(
+    out: &mut String,
+    series: &TimeSeries,
+    unit: &str,
+    lines: &[(&str, &str, Vec<Option<f64>>)],
+    empty: &str,
+) {
+    let top = lines
+        .iter()
+        .flat_map(|(_, _, values)| values.iter().flatten())
+        .copied()
+        .fold(0.0, f64::max);
+    if top == 0.0 {
+        let _ = writeln!(out, "<p>{}</p>", empty);
+        return;
+    }
+
+    let mut svg = chart_frame(series, top, unit);
+    for (_, color, values) in lines {
+        // Buckets without requests break the line instead of dropping to zero
+        let mut segment = Vec::new();
+        for (i, value) in values.iter().enumerate() {
+            match value {
+                Some(v) => segment.push(format!("{:.1},{:.1}", x(series, i, 0.5), y(*v, top))),
+                None => flush_polyline(&mut svg, &mut segment, color),
+            }
+        }
+        flush_polyline(&mut svg, &mut segment, color);
+    }
+    svg.push_str("</svg>\n");
+    out.push_str(&svg);
+    legend(
+        out,
+        &lines
+            .iter()
+            .map(|(name, color, _)| (*name, *color))
+            .collect::<Vec<_>>(),
+    );
+}
+
+fn flush_polyline(svg: &mut String, segment: &mut Vec<String>, color: &str) {
+    if segment.is_empty() {
+        return;
+    }
+    let _ = writeln!(
+        svg,
+        "<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\" points=\"{}\"/>",
+        color,
+        segment.join(" ")
+    );
+    segment.clear();
+}
+
+/// Requests per second per bucket, with the failed share stacked on top.
+fn throughput_chart(out: &mut String, series: &TimeSeries) {
+    let seconds = series.width().as_secs_f64();
+    let rate = |n: usize| n as f64 / seconds;
+    let top = series
+        .buckets()
+        .iter()
+        .map(|b| rate(b.requests))
+        .fold(0.0, f64::max);
+    if top == 0.0 {
+        out.push_str("<p>No requests recorded.</p>\n");
+        return;
+    }
+
+    let mut svg = chart_frame(series, top, "req/s");
+    let bar = (x(series, 1, 0.0) - x(series, 0, 0.0)).max(1.0);
+    for (i, bucket) in series.buckets().iter().enumerate() {
+        let ok = rate(bucket.requests - bucket.failed);
+        let failed = rate(bucket.failed);
+        let left = x(series, i, 0.0);
+        if ok > 0.0 {
+            let _ = writeln!(
+                svg,
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"#27ae60\"/>",
+                left,
+                y(ok, top),
+                bar,
+                y(0.0, top) - y(ok, top)
+            );
+        }
+        if failed > 0.0 {
+            let _ = writeln!(
+                svg,
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"#c0392b\"/>",
+                left,
+                y(ok + failed, top),
+                bar,
+                y(ok, top) - y(ok + failed, top)
+            );
+        }
+    }
+    svg.push_str("</svg>\n");
+    out.push_str(&svg);
+    legend(out, &[("successful", "#27ae60"), ("failed", "#c0392b")]);
+}
+
+/// Opening `<svg>` tag with axes, horizontal grid lines and time labels.
+fn chart_frame(series: &TimeSeries, top: f64, unit: &str) -> String {
+    let mut svg = format!(
+        "<svg viewBox=\"0 0 {} {}\" width=\"100%\" role=\"img\">\n",
+        CHART_WIDTH, CHART_HEIGHT
+    );
+    for step in 0..=4 {
+        let value = top * step as f64 / 4.0;
+        let _ = writeln!(
+            svg,
+            "<line x1=\"{}\" x2=\"{}\" y1=\"{:.1}\" y2=\"{:.1}\" stroke=\"#eee\"/><text x=\"{}\" y=\"{:.1}\" text-anchor=\"end\">{:.0} {}</text>",
+            MARGIN_LEFT,
+            CHART_WIDTH,
+            y(value, top),
+            y(value, top),
+            MARGIN_LEFT - 5.0,
+            y(value, top) + 4.0,
+            value,
+            unit
+        );
+    }
+    let total = series.width().as_secs_f64() * series.buckets().len() as f64;
+    for step in 0..=4 {
+        let seconds = total * step as f64 / 4.0;
+        let left = MARGIN_LEFT + (CHART_WIDTH - MARGIN_LEFT) * step as f64 / 4.0;
+        let _ = writeln!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{}\" text-anchor=\"middle\">{}</text>",
+            left.min(CHART_WIDTH - 20.0),
+            CHART_HEIGHT - 8.0,
+            clock(seconds)
+        );
+    }
+    svg
+}
+
+fn x(series: &TimeSeries, index: usize, offset: f64) -> f64 {
+    let slots = series.buckets().len().max(1) as f64;
+    MARGIN_LEFT + (CHART_WIDTH - MARGIN_LEFT) * (index as f64 + offset) / slots
+}
+
+fn y(value: f64, top: f64) -> f64 {
+    let height = CHART_HEIGHT - MARGIN_BOTTOM - 10.0;
+    10.0 + height * (1.0 - value / top)
+}
+
+fn clock(seconds: f64) -> String {
+    let secs = seconds.round() as u64;
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+fn legend(out: &mut String, entries: &[(&str, &str)]) {
+    out.push_str("<p class=\"legend\">");
+    for (name, color) in entries {
+        let _ = write!(
+            out,
+            "<span><i style=\"background:{}\"></i>{}</span>",
+            color, name
+        );
+    }
+    out.push_str("</p>\n");
+}
+
+fn status_codes(out: &mut String, stats: &Stats) {
+    out.push_str("<h2>Status codes</h2>\n<table>\n<tr><th>Status</th><th class=\"num\">Responses</th><th class=\"num\">Share</th></tr>\n");
+    let mut codes: Vec<(&u16, &usize)> = stats.status_codes.iter().collect();
+    codes.sort();
+    for (code, count) in codes {
+        let label = match code {
+            0 => "no response".to_string(),
+            code => code.to_string(),
+        };
+        let _ = writeln!(
+            out,
+            "<tr><td{}>{}</td><td class=\"num\">{}</td><td class=\"num\">{:.1} %</td></tr>",
+            if *code == 0 || *code >= 400 {
+                " class=\"bad\""
+            } else {
+                ""
+            },
+            label,
+            count,
+            *count as f64 / stats.transactions.max(1) as f64 * 100.0
+        );
+    }
+    out.push_str("</table>\n");
+}
+
+fn errors(out: &mut String, stats: &Stats) {
+    out.push_str("<h2>Errors</h2>\n");
+    if stats.errors.is_empty() {
+        out.push_str("<p>Every request got a response.</p>\n");
+        return;
+    }
+    let mut errors: Vec<(&ErrorCategory, &usize)> = stats.errors.iter().collect();
+    errors.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    out.push_str("<table>\n<tr><th>Cause</th><th class=\"num\">Requests</th></tr>\n");
+    for (category, count) in errors {
+        let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td class=\"num\">{}</td></tr>",
+            category, count
+        );
+    }
+    out.push_str("</table>\n");
+}
+
+fn cache(out: &mut String, stats: &Stats) {
+    out.push_str("<h2>Cache</h2>\n");
+    let cached = stats.cache_hits + stats.cache_misses;
+    if cached == 0 {
+        out.push_str("<p>No responses carried a cache status header.</p>\n");
+        return;
+    }
+    let _ = writeln!(
+        out,
+        "<p>Hit ratio <strong>{}</strong>: {} hits and {} misses out of {} responses with a cache status header.</p>",
+        cache_ratio(stats),
+        stats.cache_hits,
+        stats.cache_misses,
+        cached
+    );
+}
+
+fn slowest_urls(out: &mut String, stats: &Stats) {
+    let mut urls: Vec<(&String, &UrlStats)> = stats.urls.iter().collect();
+    urls.sort_by(|a, b| {
+        b.1.avg_response_time()
+            .partial_cmp(&a.1.avg_response_time())
+            .unwrap()
+            .then(a.0.cmp(b.0))
+    });
+    urls.truncate(SLOWEST);
+    let _ = writeln!(out, "<h2>Slowest URLs</h2>");
+    url_rows(out, &urls);
+}
+
+fn url_table(out: &mut String, stats: &Stats) {
+    let mut urls: Vec<(&String, &UrlStats)> = stats.urls.iter().collect();
+    urls.sort_by(|a, b| b.1.is_main_url.cmp(&a.1.is_main_url).then(a.0.cmp(b.0)));
+    let _ = writeln!(out, "<h2>All URLs ({})</h2>", urls.len());
+    url_rows(out, &urls);
+}
+
+fn url_rows(out: &mut String, urls: &[(&String, &UrlStats)]) {
+    out.push_str("<table>\n<tr><th>URL</th><th>Type</th><th class=\"num\">Requests</th><th class=\"num\">Failed</th><th class=\"num\">Avg ms</th><th class=\"num\">p95 ms</th><th class=\"num\">Max ms</th><th>Status</th></tr>\n");
+    for (url, stats) in urls {
+        let statuses: Vec<String> = stats
+            .status_codes
+            .iter()
+            .map(|(code, count)| match code {
+                0 => format!("error &times;{}", count),
+                code => format!("{} &times;{}", code, count),
+            })
+            .collect();
+        let mut status = statuses.join(", ");
+        if let Some(ref error) = stats.last_error {
+            let _ = write!(status, "<br>{}: {}", error.category, escape(&error.message));
+        }
+        let _ = writeln!(
+            out,
+            "<tr><td class=\"url\">{}</td><td>{}</td><td class=\"num\">{}</td><td class=\"num{}\">{}</td><td class=\"num\">{:.0}</td><td class=\"num\">{:.0}</td><td class=\"num\">{:.0}</td><td>{}</td></tr>",
+            escape(url),
+            if stats.is_main_url { "page" } else { "asset" },
+            stats.requests,
+            if stats.failed > 0 { " bad" } else { "" },
+            stats.failed,
+            stats.avg_response_time(),
+            stats.latency.percentile(95.0).unwrap_or(0.0),
+            stats.max_response_time(),
+            status
+        );
+    }
+    out.push_str("</table>\n");
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,120 @@
+use crate::histogram::Histogram;
+use crate::{ErrorCategory, Stats};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// What happened between two reads of `Stats`.
+pub struct IntervalStats {
+    /// Run time at the end of the interval, in seconds
+    pub elapsed: f64,
+    /// Length of the interval, in seconds
+    pub seconds: f64,
+    pub requests: usize,
+    pub failed: usize,
+    pub bytes: u64,
+    pub cache_hits: usize,
+    pub cache_misses: usize,
+    /// Errors by category, most frequent first
+    pub errors: Vec<(ErrorCategory, usize)>,
+    /// Response times of the interval, in ms
+    pub latency: Histogram,
+}
+
+impl IntervalStats {
+    pub fn rate(&self) -> f64 {
+        if self.seconds > 0.0 {
+            self.requests as f64 / self.seconds
+        } else {
+            0.0
+        }
+    }
+
+    /// Latency percentile in ms; None when the interval had no requests.
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        self.latency.percentile(p)
+    }
+
+    pub fn avg(&self) -> Option<f64> {
+        self.latency.avg()
+    }
+
+    /// Slowest response of the interval, estimated from the histogram.
+    pub fn max(&self) -> Option<f64> {
+        self.latency.max()
+    }
+}
+
+/// Remembers the `Stats` counters at the end of the previous interval.
+pub struct IntervalTracker {
+    at: Instant,
+    transactions: usize,
+    failed: usize,
+    bytes: u64,
+    cache_hits: usize,
+    cache_misses: usize,
+    errors: HashMap<ErrorCategory, usize>,
+    latency: Histogram,
+}
+
+impl IntervalTracker {
+    pub fn new() -> Self {
+        Self {
+            at: Instant::now(),
+            transactions: 0,
+            failed: 0,
+            bytes: 0,
+            cache_hits: 0,
+            cache_misses: 0,
+            errors: HashMap::new(),
+            latency: Histogram::default(),
+        }
+    }
+
+    /// Close the current interval and start the next one.
+    pub fn next(&mut self, stats: &Mutex<Stats>) -> IntervalStats {
+        let (elapsed, current) = {
+            let stats = stats.lock().unwrap();
+            (
+                stats.elapsed_time(),
+                Self {
+                    at: Instant::now(),
+                    transactions: stats.transactions,
+                    failed: stats.failed_transactions,
+                    bytes: stats.data_transferred,
+                    cache_hits: stats.cache_hits,
+                    cache_misses: stats.cache_misses,
+                    errors: stats.errors.clone(),
+                    latency: stats.latency.clone(),
+                },
+            )
+        };
+
+        let mut errors: Vec<(ErrorCategory, usize)> = current
+            .errors
+            .iter()
+            .map(|(category, &n)| {
+                (
+                    *category,
+                    n - self.errors.get(category).copied().unwrap_or(0),
+                )
+            })
+            .filter(|(_, n)| *n > 0)
+            .collect();
+        errors.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let interval = IntervalStats {
+            elapsed,
+            seconds: current.at.duration_since(self.at).as_secs_f64(),
+            requests: current.transactions - self.transactions,
+            failed: current.failed - self.failed,
+            bytes: current.bytes - self.bytes,
+            cache_hits: current.cache_hits - self.cache_hits,
+            cache_misses: current.cache_misses - self.cache_misses,
+            errors,
+            latency: current.latency.since(&self.latency),
+        };
+        *self = current;
+        interval
+    }
+}
@@ -1,11 +1,15 @@
-use crate::Stats;
-use headless_chrome::{Browser, LaunchOptions};
-use isahc::{
-    Request,
-    config::{RedirectPolicy, SslOption},
-    prelude::*,
+use crate::asset_policy::AssetType;
+use crate::crawl_scope::CrawlLimits;
+use crate::crawl_state::{Outcome, Phase};
+use crate::robots::Robots;
+use crate::{
+    RequestError, RequestResult, Stats, asset_policy, cache_hit, crawl_state,
+    isahc_redirect_policy, normalize_url, output, rate_limits, record_result, shutdown, url_filter,
 };
-use serde_json;
+use headless_chrome::{Browser, LaunchOptions};
+use isahc::{Request, config::SslOption, prelude::*};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use url::Url;
 
@@ -15,17 +19,17 @@ pub async fn crawl_js_site(
     concurrency: usize,
     stats: Arc<Mutex<Stats>>,
     discovery_threads: Option<usize>,
+    robots: Arc<Robots>,
+    limits: Arc<CrawlLimits>,
 ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
     println!(
         "JavaScript mode: Starting headless Chrome browser to crawl from {}",
         start_url
     );
 
-    // Extract base host for filtering; require a valid host from the provided URL
-    let base_host = if let Ok(parsed_url) = Url::parse(start_url) {
-        if let Some(host) = parsed_url.host_str() {
-            host.to_string()
-        } else {
+    // Require a valid host from the provided URL; the crawl scope is built around it
+    if let Ok(parsed_url) = Url::parse(start_url) {
+        if parsed_url.host_str().is_none() {
             return Err(
                 "Invalid URL: missing host. Please provide a full URL (e.g., https://example.com)"
                     .into(),
@@ -36,18 +40,29 @@ pub async fn crawl_js_site(
             "Invalid URL: unable to parse. Please provide a full URL (e.g., https://example.com)"
                 .into(),
         );
-    };
+    }
+
+    if !robots.is_allowed(start_url) {
+        return Err(format!(
+            "{} is disallowed by robots.txt (use --ignore-robots for your own sites)",
+            start_url
+        )
+        .into());
+    }
 
     // Global collections to track everything
-    let all_discovered_urls = Arc::new(Mutex::new(std::collections::HashSet::new()));
+    // Pages are deduplicated on their normalized URL but requested as they were linked
+    let all_discovered_urls: Arc<Mutex<HashMap<String, String>>> =
+        Arc::new(Mutex::new(HashMap::new()));
     let all_discovered_assets = Arc::new(Mutex::new(std::collections::HashSet::new()));
     let visited_urls = Arc::new(Mutex::new(std::collections::HashSet::new()));
 
     // Function to discover URLs and assets from a single page
     fn discover_page(
         url: &str,
-        base_host: &str,
+        limits: &CrawlLimits,
         browser: &Browser,
+        robots: &Robots,
     ) -> Result<(Vec<String>, Vec<String>), Box<dyn std::error::Error>> {
         // JavaScript for extracting links
         let links_js = r#"
@@ -94,6 +109,37 @@ pub async fn crawl_js_site(
                     if (link.href) assets.push(link.href);
                 });
 
+                // Responsive image candidates actually chosen by the browser
+                document.querySelectorAll('img').forEach(img => {
+                    if (img.currentSrc) assets.push(img.currentSrc);
+                });
+                document.querySelectorAll('source[srcset]').forEach(source => {
+                    source.srcset.split(',').forEach(candidate => {
+                        const src = candidate.trim().split(/\s+/)[0];
+                        if (src) assets.push(src);
+                    });
+                });
+
+                // Video/audio sources and posters
+                document.querySelectorAll('video[src], audio[src], source[src], track[src]').forEach(media => {
+                    if (media.src) assets.push(media.src);
+                });
+                document.querySelectorAll('video[poster]').forEach(video => {
+                    if (video.poster) assets.push(video.poster);
+                });
+
+                // Social share images
+                document.querySelectorAll('meta[property="og:image"], meta[name="twitter:image"]').forEach(meta => {
+                    if (meta.content) assets.push(meta.content);
+                });
+
+                // Everything else the page loaded: fonts and background images from CSS,
+                // preloads and lazily inserted media
+                const initiators = ['link', 'script', 'img', 'css', 'video', 'audio', 'track'];
+                performance.getEntriesByType('resource').forEach(entry => {
+                    if (initiators.includes(entry.initiatorType)) assets.push(entry.name);
+                });
+
                 return assets.map(asset => {
                     try {
                         return new URL(asset, window.location.href).href;
@@ -124,10 +170,10 @@ pub async fn crawl_js_site(
                 if let Some(preview) = &links_result.preview {
                     let mut links = Vec::new();
                     for prop in &preview.properties {
-                        if let Some(value) = &prop.value {
-                            if value.starts_with("http") {
-                                links.push(value.clone());
-                            }
+                        if let Some(value) = &prop.value
+                            && value.starts_with("http")
+                        {
+                            links.push(value.clone());
                         }
                     }
                     links
@@ -146,10 +192,10 @@ pub async fn crawl_js_site(
                 if let Some(preview) = &assets_result.preview {
                     let mut assets = Vec::new();
                     for prop in &preview.properties {
-                        if let Some(value) = &prop.value {
-                            if value.starts_with("http") {
-                                assets.push(value.clone());
-                            }
+                        if let Some(value) = &prop.value
+                            && value.starts_with("http")
+                        {
+                            assets.push(value.clone());
                         }
                     }
                     assets
@@ -159,26 +205,38 @@ pub async fn crawl_js_site(
             }
         };
 
-        // Filter same-host links
-        let same_host_links: Vec<String> = page_links
+        // Keep in-scope links that robots.txt and the exclude patterns allow
+        let in_scope_links: Vec<String> = page_links
             .into_iter()
             .filter(|link| {
-                if let Ok(parsed_url) = Url::parse(link) {
-                    if let Some(link_host) = parsed_url.host_str() {
-                        return link_host == base_host;
-                    }
-                }
-                false
+                limits.in_scope(link) && robots.is_allowed(link) && !url_filter().is_excluded(link)
             })
             .collect();
+        let mut page_assets: Vec<String> = page_assets
+            .into_iter()
+            .filter(|asset| {
+                robots.is_allowed(asset)
+                    && !url_filter().is_excluded(asset)
+                    // The browser only reports URLs, so type by extension; extensionless
+                    // resources are mostly images served by transform endpoints
+                    && asset_policy().allows(
+                        asset,
+                        AssetType::from_url(asset).unwrap_or(AssetType::Image),
+                        url,
+                    )
+            })
+            .collect();
+        // The same file is often seen both in the DOM and in the resource timings
+        page_assets.sort();
+        page_assets.dedup();
 
         println!(
-            "Discovered {} same-host links and {} assets from {}",
-            same_host_links.len(),
+            "Discovered {} in-scope links and {} assets from {}",
+            in_scope_links.len(),
             page_assets.len(),
             url
         );
-        Ok((same_host_links, page_assets))
+        Ok((in_scope_links, page_assets))
     }
 
     // Function to load test assets using HTTP requests
@@ -201,33 +259,37 @@ pub async fn crawl_js_site(
             let stats = stats.clone();
 
             let handle = std::thread::spawn(move || {
-                loop {
+                // On Ctrl-C, stop taking assets off the queue
+                while !shutdown::requested() {
                     let asset = {
                         let mut assets = assets.lock().unwrap();
                         assets.pop()
                     };
 
                     if let Some(url) = asset {
+                        // Honor --asset-rate / --host-asset-rate
+                        let wait = rate_limits().reserve(&url, false);
+                        if !shutdown::sleep_blocking_unless_stopped(wait) {
+                            break;
+                        }
+
                         // Perform the HTTP request
                         let start_time = std::time::Instant::now();
+                        let started_at = chrono::Utc::now();
                         let response = Request::get(&url)
                             .ssl_options(
                                 SslOption::DANGER_ACCEPT_INVALID_CERTS
                                     | SslOption::DANGER_ACCEPT_REVOKED_CERTS
                                     | SslOption::DANGER_ACCEPT_INVALID_HOSTS,
                             )
-                            .redirect_policy(RedirectPolicy::Follow)
+                            .redirect_policy(isahc_redirect_policy())
                             .body(())
-                            .map_err(|e| format!("Request creation failed: {}", e))
-                            .and_then(|req| {
-                                req.send()
-                                    .map_err(|e| format!("Request send failed: {}", e))
-                            });
+                            .map_err(|e| RequestError::invalid_request(&e))
+                            .and_then(|req| req.send().map_err(|e| RequestError::from_isahc(&e)));
 
                         let elapsed = start_time.elapsed();
-                        let mut stats = stats.lock().unwrap();
 
-                        match response {
+                        let result = match response {
                             Ok(response) => {
                                 let status = response.status();
                                 let content_length = response
@@ -237,30 +299,47 @@ pub async fn crawl_js_site(
                                     .and_then(|s| s.parse::<usize>().ok())
                                     .unwrap_or(0);
 
-                                stats.add_transaction(
-                                    elapsed.as_millis() as f64,
-                                    content_length as u64,
-                                    status.as_u16(),
-                                );
-                                println!(
-                                    "HTTP/{} {}     {:.2} secs: {} KB ==> GET  {}",
-                                    status.as_str().chars().next().unwrap_or('?'),
-                                    status.as_str(),
-                                    elapsed.as_secs_f64(),
-                                    content_length / 1024,
-                                    url
-                                );
+                                let result = RequestResult {
+                                    url: url.clone(),
+                                    started_at,
+                                    status_code: status.as_u16(),
+                                    response_time: elapsed.as_millis() as f64,
+                                    data_size: content_length as u64,
+                                    body: None,
+                                    http_version: format!("{:?}", response.version()),
+                                    error: None,
+                                    redirects: Vec::new(),
+                                    redirect_stopped: None,
+                                    cache_hit: cache_hit(response.headers()),
+                                    is_main_url: false,
+                                    timings: None,
+                                };
+                                if output::shows_requests() {
+                                    println!(
+                                        "HTTP/{} {}     {:.2} secs: {} KB ==> GET  {}",
+                                        status.as_str().chars().next().unwrap_or('?'),
+                                        status.as_str(),
+                                        elapsed.as_secs_f64(),
+                                        content_length / 1024,
+                                        url
+                                    );
+                                }
+                                result
                             }
                             Err(e) => {
-                                stats.add_transaction(elapsed.as_millis() as f64, 0, 0);
-                                println!(
-                                    "HTTP/1.1 0     {:.2} secs: 0 bytes ==> GET  {} (Error: {})",
-                                    elapsed.as_secs_f64(),
-                                    url,
-                                    e
-                                );
+                                if output::shows_requests() {
+                                    println!(
+                                        "HTTP/1.1 0     {:.2} secs: 0 bytes ==> GET  {} (Error: {}: {})",
+                                        elapsed.as_secs_f64(),
+                                        url,
+                                        e.category,
+                                        e.message
+                                    );
+                                }
+                                RequestResult::failed(&url, elapsed.as_millis() as f64, e)
                             }
-                        }
+                        };
+                        record_result(&stats, &result);
                     } else {
                         break;
                     }
@@ -276,8 +355,7 @@ pub async fn crawl_js_site(
     }
 
     // Calculate discovery threads (use provided value or default to CPU cores / 2, min 2, max 8)
-    let discovery_threads = discovery_threads
-        .unwrap_or_else(|| std::cmp::min(8, std::cmp::max(2, num_cpus::get() / 2)));
+    let discovery_threads = discovery_threads.unwrap_or_else(|| (num_cpus::get() / 2).clamp(2, 8));
     println!(
         "Using {} discovery threads for parallel page crawling (CPU cores: {})",
         discovery_threads,
@@ -288,9 +366,34 @@ pub async fn crawl_js_site(
     let urls_to_process = Arc::new(Mutex::new(std::collections::VecDeque::new()));
     let active_threads = Arc::new(Mutex::new(discovery_threads));
     {
+        // Queue entries carry their link depth (the start URL is depth 0). A resumed run
+        // skips the pages its interrupted run rendered and continues with its queue.
         let mut queue = urls_to_process.lock().unwrap();
-        queue.push_back(start_url.to_string());
+        let (resumed, pending) = crawl_state()
+            .map(|s| s.resume_point(Phase::Discovery))
+            .unwrap_or_default();
+        if resumed.is_empty() && pending.is_empty() {
+            if let Some(state) = crawl_state() {
+                state.queue(Phase::Discovery, start_url, 0);
+            }
+            queue.push_back((start_url.to_string(), 0));
+        } else {
+            let mut all_urls = all_discovered_urls.lock().unwrap();
+            all_urls.extend(resumed.iter().map(|url| (normalize_url(url), url.clone())));
+            all_urls.extend(
+                pending
+                    .iter()
+                    .map(|(url, _)| (normalize_url(url), url.clone())),
+            );
+            visited_urls
+                .lock()
+                .unwrap()
+                .extend(resumed.iter().map(|url| normalize_url(url)));
+            queue.extend(pending);
+        }
     }
+    let page_limit_hit = Arc::new(AtomicBool::new(false));
+    let depth_limited = Arc::new(AtomicUsize::new(0));
 
     // Start discovery threads
     let mut discovery_handles = Vec::new();
@@ -301,7 +404,10 @@ pub async fn crawl_js_site(
         let all_discovered_assets = all_discovered_assets.clone();
         let active_threads = active_threads.clone();
         let stats = stats.clone();
-        let base_host = base_host.clone();
+        let limits = limits.clone();
+        let robots = robots.clone();
+        let page_limit_hit = page_limit_hit.clone();
+        let depth_limited = depth_limited.clone();
 
         let handle = std::thread::spawn(move || {
             // Each thread gets its own browser instance
@@ -319,13 +425,18 @@ pub async fn crawl_js_site(
             };
 
             loop {
+                // On Ctrl-C, stop rendering pages; queued ones stay in the state file
+                if shutdown::requested() {
+                    break;
+                }
+
                 let current_url = {
                     let mut queue = urls_to_process.lock().unwrap();
                     queue.pop_front()
                 };
 
-                let current_url = match current_url {
-                    Some(url) => url,
+                let (current_url, depth) = match current_url {
+                    Some(entry) => entry,
                     None => {
                         // No more URLs available right now
                         // Check if there are other active threads that might add URLs
@@ -355,14 +466,25 @@ pub async fn crawl_js_site(
                 // Skip if already visited
                 {
                     let mut visited = visited_urls.lock().unwrap();
-                    if visited.contains(&current_url) {
+                    if !visited.insert(normalize_url(&current_url)) {
                         continue;
                     }
-                    visited.insert(current_url.clone());
+                }
+
+                // Honor --page-rate and robots.txt Crawl-delay across all discovery
+                // threads; a page still waiting on Ctrl-C stays queued in the state file
+                if !shutdown::sleep_blocking_unless_stopped(
+                    rate_limits().reserve(&current_url, true),
+                ) {
+                    break;
                 }
 
                 // 1. Discover URLs and assets from this page
-                match discover_page(&current_url, &base_host, &browser) {
+                let mut outcome = Outcome {
+                    status: None,
+                    error: None,
+                };
+                match discover_page(&current_url, &limits, &browser, &robots) {
                     Ok((page_urls, page_assets)) => {
                         // 2. Load test the discovered assets immediately
                         load_test_assets(page_assets.clone(), stats.clone(), concurrency);
@@ -374,8 +496,22 @@ pub async fn crawl_js_site(
                             let mut queue = urls_to_process.lock().unwrap();
 
                             for url in &page_urls {
-                                if all_urls.insert(url.clone()) {
-                                    queue.push_back(url.clone());
+                                let key = normalize_url(url);
+                                if all_urls.contains_key(&key) {
+                                    continue;
+                                }
+                                if !limits.depth_allows(depth) {
+                                    depth_limited.fetch_add(1, Ordering::Relaxed);
+                                    continue;
+                                }
+                                if limits.pages_reached(all_urls.len()) {
+                                    page_limit_hit.store(true, Ordering::Relaxed);
+                                    break;
+                                }
+                                all_urls.insert(key, url.clone());
+                                queue.push_back((url.clone(), depth + 1));
+                                if let Some(state) = crawl_state() {
+                                    state.queue(Phase::Discovery, url, depth + 1);
                                 }
                             }
 
@@ -389,8 +525,14 @@ pub async fn crawl_js_site(
                             "Discovery thread {} failed to process {}: {}",
                             i, current_url, e
                         );
+                        outcome.error = Some(e.to_string());
                     }
                 }
+
+                if let Some(state) = crawl_state() {
+                    state.record(Phase::Discovery, &current_url, outcome);
+                    state.checkpoint();
+                }
             }
 
             // Thread is exiting, decrement active count
@@ -416,7 +558,12 @@ pub async fn crawl_js_site(
     let all_urls = all_discovered_urls.lock().unwrap();
     let all_assets = all_discovered_assets.lock().unwrap();
 
-    let mut combined: Vec<String> = all_urls.iter().cloned().collect();
+    // Pages outside the include patterns were crawled for their links but aren't warmed
+    let mut combined: Vec<String> = all_urls
+        .values()
+        .filter(|u| url_filter().allows(u))
+        .cloned()
+        .collect();
     combined.extend(all_assets.iter().cloned());
     combined.sort();
     combined.dedup();
@@ -426,6 +573,10 @@ pub async fn crawl_js_site(
         all_urls.len(),
         all_assets.len()
     );
+    limits.report(
+        page_limit_hit.load(Ordering::Relaxed),
+        depth_limited.load(Ordering::Relaxed),
+    );
 
     Ok(combined)
 }
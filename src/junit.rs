@@ -0,0 +1,348 @@
+use crate::thresholds::Verdict;
+use crate::{Stats, UrlStats};
+use std::fmt::Write as _;
+use url::Url;
+
+/// One `<testcase>`: a requested URL.
+struct Case<'a> {
+    url: &'a str,
+    stats: &'a UrlStats,
+}
+
+impl Case<'_> {
+    /// Statuses outside 2xx/3xx, as (status, responses).
+    fn bad_statuses(&self) -> Vec<(u16, usize)> {
+        self.stats
+            .status_codes
+            .iter()
+            .filter(|(code, _)| **code != 0 && !(200..400).contains(*code))
+            .map(|(code, count)| (*code, *count))
+            .collect()
+    }
+
+    fn is_error(&self) -> bool {
+        self.stats.last_error.is_some()
+    }
+
+    fn is_failure(&self) -> bool {
+        !self.is_error() && !self.bad_statuses().is_empty()
+    }
+}
+
+/// Render a JUnit XML report with one test suite for pages and one for assets, and
+/// one test case per requested URL. A URL fails when any of its responses was not
+/// 2xx/3xx, and errors when any of its requests got no response. Thresholds get a
+/// suite of their own.
+pub fn render(stats: &Stats, title: &str, verdicts: &[Verdict]) -> String {
+    let mut suites: Vec<(&str, Vec<Case>)> = vec![("pages", Vec::new()), ("assets", Vec::new())];
+    let mut urls: Vec<(&String, &UrlStats)> = stats.urls.iter().collect();
+    urls.sort_by(|a, b| a.0.cmp(b.0));
+    for (url, url_stats) in urls {
+        let suite = if url_stats.is_main_url { 0 } else { 1 };
+        suites[suite].1.push(Case {
+            url,
+            stats: url_stats,
+        });
+    }
+    suites.retain(|(_, cases)| !cases.is_empty());
+
+    let threshold_failures = verdicts.iter().filter(|v| !v.passed).count();
+    let tests: usize = suites.iter().map(|(_, cases)| cases.len()).sum::<usize>() + verdicts.len();
+    let failures: usize = suites
+        .iter()
+        .map(|(_, cases)| count(cases, |c| c.is_failure()))
+        .sum::<usize>()
+        + threshold_failures;
+    let errors: usize = suites
+        .iter()
+        .map(|(_, cases)| count(cases, |c| c.is_error()))
+        .sum();
+    let timestamp = stats.started_at.format("%Y-%m-%dT%H:%M:%S");
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        out,
+        "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">",
+        escape(&format!("warmer {}", title)),
+        tests,
+        failures,
+        errors,
+        stats.elapsed_time()
+    );
+    for (name, cases) in &suites {
+        let time: f64 = cases.iter().map(|c| c.stats.latency.sum() / 1000.0).sum();
+        let _ = writeln!(
+            out,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"0\" time=\"{:.3}\" timestamp=\"{}\">",
+            name,
+            cases.len(),
+            count(cases, |c| c.is_failure()),
+            count(cases, |c| c.is_error()),
+            time,
+            timestamp
+        );
+        for case in cases {
+            test_case(&mut out, case);
+        }
+        out.push_str("  </testsuite>\n");
+    }
+    if !verdicts.is_empty() {
+        let _ = writeln!(
+            out,
+            "  <testsuite name=\"thresholds\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"0\" time=\"0\" timestamp=\"{}\">",
+            verdicts.len(),
+            threshold_failures,
+            timestamp
+        );
+        for verdict in verdicts {
+            let _ = writeln!(
+                out,
+                "    <testcase classname=\"warmer.thresholds\" name=\"{}\" time=\"0\">",
+                escape(verdict.expression())
+            );
+            if !verdict.passed {
+                let _ = writeln!(
+                    out,
+                    "      <failure type=\"threshold\" message=\"{}\"/>",
+                    escape(&format!("actual {}", verdict.actual()))
+                );
+            }
+            out.push_str("    </testcase>\n");
+        }
+        out.push_str("  </testsuite>\n");
+    }
+    out.push_str("</testsuites>\n");
+    out
+}
+
+fn count(cases: &[Case], outcome: impl Fn(&Case) -> bool) -> usize {
+    cases.iter().filter(|case| outcome(case)).count()
+}
+
+fn test_case(out: &mut String, case: &Case) {
+    let stats = case.stats;
+    let classname = Url::parse(case.url)
+        .ok()
+        .and_then(|u| {
+            u.host_str()
+                .map(|h| format!("warmer.{}", h.replace('.', "_")))
+        })
+        .unwrap_or_else(|| "warmer".to_string());
+    let _ = writeln!(
+        out,
+        "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\">",
+        escape(&classname),
+        escape(case.url),
+        stats.avg_response_time() / 1000.0
+    );
+
+    let statuses: Vec<String> = stats
+        .status_codes
+        .iter()
+        .map(|(code, n)| match code {
+            0 => format!("no response x{}", n),
+            code => format!("{} x{}", code, n),
+        })
+        .collect();
+    if let Some(ref error) = stats.last_error {
+        let _ = writeln!(
+            out,
+            "      <error type=\"{}\" message=\"{}\">{}</error>",
+            error.category.tag(),
+            escape(&format!(
+                "{} of {} requests got no response: {}",
+                stats.status_codes.get(&0).copied().unwrap_or(0),
+                stats.requests,
+                error.category
+            )),
+            escape(&error.message)
+        );
+    } else if case.is_failure() {
+        let bad = case.bad_statuses();
+        let codes: Vec<String> = bad.iter().map(|(code, _)| code.to_string()).collect();
+        let _ = writeln!(
+            out,
+            "      <failure type=\"status\" message=\"{}\">{}</failure>",
+            escape(&format!(
+                "HTTP {} on {} of {} requests",
+                codes.join(", "),
+                bad.iter().map(|(_, n)| n).sum::<usize>(),
+                stats.requests
+            )),
+            escape(&statuses.join(", "))
+        );
+    }
+    let _ = writeln!(
+        out,
+        "      <system-out>{}</system-out>",
+        escape(&format!(
+            "{} requests ({}), avg {:.0} ms, max {:.0} ms, {} bytes",
+            stats.requests,
+            statuses.join(", "),
+            stats.avg_response_time(),
+            stats.max_response_time(),
+            stats.bytes
+        ))
+    );
+    out.push_str("    </testcase>\n");
+}
+
+/// Escape text for an attribute or element. Control characters XML doesn't allow,
+/// as found in some error messages, become U+FFFD.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\t' => escaped.push(c),
+            c if c < ' ' || c == '\u{fffe}' || c == '\u{ffff}' => escaped.push('\u{fffd}'),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thresholds::Threshold;
+    use crate::{ErrorCategory, RequestError};
+
+    fn url_stats(is_main_url: bool, statuses: &[u16], error: Option<&str>) -> UrlStats {
+        let mut stats = UrlStats {
+            is_main_url,
+            requests: statuses.len(),
+            last_error: error.map(|message| RequestError {
+                category: ErrorCategory::Timeout,
+                message: message.to_string(),
+            }),
+            ..Default::default()
+        };
+        for &status in statuses {
+            *stats.status_codes.entry(status).or_insert(0) += 1;
+            stats.latency.record(120.0);
+            if status == 0 || status >= 400 {
+                stats.failed += 1;
+            }
+        }
+        stats
+    }
+
+    fn run() -> Stats {
+        let mut stats = Stats::default();
+        let urls = [
+            ("https://example.com/", url_stats(true, &[200, 200], None)),
+            (
+                "https://example.com/gone",
+                url_stats(true, &[200, 404], None),
+            ),
+            (
+                "https://example.com/slow",
+                url_stats(true, &[0], Some("timed out\u{1}\r<again>")),
+            ),
+            ("https://example.com/a.css", url_stats(false, &[304], None)),
+            (
+                "https://cdn.example.net/b.js",
+                url_stats(false, &[500], None),
+            ),
+        ];
+        for (url, url_stats) in urls {
+            stats.transactions += url_stats.requests;
+            stats.urls.insert(url.to_string(), url_stats);
+        }
+        stats
+    }
+
+    fn attr<'a>(tag: &'a str, name: &str) -> &'a str {
+        let start = tag.find(&format!(" {}=\"", name)).unwrap() + name.len() + 3;
+        let len = tag[start..].find('"').unwrap();
+        &tag[start..start + len]
+    }
+
+    fn tag<'a>(xml: &'a str, prefix: &str) -> &'a str {
+        xml.lines()
+            .map(str::trim)
+            .find(|line| line.starts_with(prefix))
+            .unwrap_or_else(|| panic!("no {} in {}", prefix, xml))
+    }
+
+    #[test]
+    fn counts_tests_failures_and_errors_per_suite() {
+        let stats = run();
+        let verdicts = [
+            Threshold::parse("requests >= 1").unwrap().check(&stats),
+            Threshold::parse("requests > 100").unwrap().check(&stats),
+        ];
+        let xml = render(&stats, "https://example.com/", &verdicts);
+
+        let root = tag(&xml, "<testsuites ");
+        assert_eq!(attr(root, "tests"), "7");
+        assert_eq!(attr(root, "failures"), "3");
+        assert_eq!(attr(root, "errors"), "1");
+
+        let pages = tag(&xml, "<testsuite name=\"pages\"");
+        assert_eq!(
+            [
+                attr(pages, "tests"),
+                attr(pages, "failures"),
+                attr(pages, "errors")
+            ],
+            ["3", "1", "1"]
+        );
+        let assets = tag(&xml, "<testsuite name=\"assets\"");
+        assert_eq!(
+            [
+                attr(assets, "tests"),
+                attr(assets, "failures"),
+                attr(assets, "errors")
+            ],
+            ["2", "1", "0"]
+        );
+        let thresholds = tag(&xml, "<testsuite name=\"thresholds\"");
+        assert_eq!(
+            [attr(thresholds, "tests"), attr(thresholds, "failures")],
+            ["2", "1"]
+        );
+        assert!(xml.contains("name=\"requests &gt; 100\""));
+        assert!(xml.contains("classname=\"warmer.cdn_example_net\""));
+    }
+
+    #[test]
+    fn empty_suites_are_left_out() {
+        let mut stats = Stats::default();
+        stats.urls.insert(
+            "https://example.com/".to_string(),
+            url_stats(true, &[200], None),
+        );
+        let xml = render(&stats, "https://example.com/", &[]);
+        assert!(xml.contains("<testsuite name=\"pages\""));
+        assert!(!xml.contains("name=\"assets\""));
+        assert!(!xml.contains("name=\"thresholds\""));
+    }
+
+    #[test]
+    fn error_messages_are_valid_xml_text() {
+        let xml = render(&run(), "https://example.com/", &[]);
+        let error = tag(&xml, "<error ");
+        assert!(
+            error.ends_with(">timed out\u{fffd}\u{fffd}&lt;again&gt;</error>"),
+            "{}",
+            error
+        );
+        assert!(!xml.chars().any(|c| c < ' ' && c != '\n'));
+    }
+
+    #[test]
+    fn escape_keeps_tabs_and_newlines() {
+        assert_eq!(escape("a\tb\nc"), "a\tb&#10;c");
+        assert_eq!(escape("\u{0}\u{1b}[31m"), "\u{fffd}\u{fffd}[31m");
+        assert_eq!(
+            escape("<a href=\"x\">&</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use crate::{normalize_url, url_norm};
+use scraper::{Html, Selector};
+use serde::Deserialize;
+
+/// Extra page relationships the crawlers can use besides `<a href>` links.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiscoverLink {
+    /// Honor `<link rel="canonical">`: warm the canonical URL instead of duplicates
+    Canonical,
+    /// Warm `<link rel="alternate" hreflang>` language alternates (and sitemap
+    /// `xhtml:link` alternates)
+    Hreflang,
+    /// Warm `<link rel="next">` / `<link rel="prev">` pagination
+    Pagination,
+}
+
+/// Which of the [`DiscoverLink`] relationships are enabled.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LinkDiscovery {
+    pub canonical: bool,
+    pub hreflang: bool,
+    pub pagination: bool,
+}
+
+/// Relationship links found in a page's `<head>`, resolved against the page.
+#[derive(Debug, Default)]
+pub struct PageHints {
+    /// Canonical URL when it differs from the page URL
+    pub canonical: Option<String>,
+    /// hreflang alternates and pagination links to queue alongside `<a href>` links
+    pub related: Vec<String>,
+}
+
+impl LinkDiscovery {
+    pub fn new(kinds: &[DiscoverLink]) -> Self {
+        Self {
+            canonical: kinds.contains(&DiscoverLink::Canonical),
+            hreflang: kinds.contains(&DiscoverLink::Hreflang),
+            pagination: kinds.contains(&DiscoverLink::Pagination),
+        }
+    }
+
+    /// Extract the enabled relationship links from a page.
+    pub fn page_hints(&self, html_content: &str, page_url: &str) -> PageHints {
+        let mut hints = PageHints::default();
+        if !self.canonical && !self.hreflang && !self.pagination {
+            return hints;
+        }
+
+        let html = Html::parse_document(html_content);
+        let Some(base) = url_norm::document_base(&html, page_url) else {
+            return hints;
+        };
+        let Ok(selector) = Selector::parse("link[rel][href]") else {
+            return hints;
+        };
+
+        for element in html.select(&selector) {
+            let link = element.value();
+            let (Some(rel), Some(href)) = (link.attr("rel"), link.attr("href")) else {
+                continue;
+            };
+            let Some(target) = url_norm::resolve(href, &base) else {
+                continue;
+            };
+            let rel = rel.to_lowercase();
+            let tokens: Vec<&str> = rel.split_whitespace().collect();
+
+            if self.canonical && tokens.contains(&"canonical") {
+                if hints.canonical.is_none() && normalize_url(&target) != normalize_url(page_url) {
+                    hints.canonical = Some(target);
+                }
+                continue;
+            }
+
+            let hreflang =
+                self.hreflang && tokens.contains(&"alternate") && link.attr("hreflang").is_some();
+            let pagination =
+                self.pagination && (tokens.contains(&"next") || tokens.contains(&"prev"));
+            if hreflang || pagination {
+                hints.related.push(target);
+            }
+        }
+
+        hints
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "https://example.com/blog/page-2?sort=new";
+
+    const HEAD: &str = r#"<html><head>
+        <link rel="canonical" href="/blog/page-2">
+        <link rel="alternate" hreflang="de" href="/de/blog/page-2">
+        <link rel="alternate" type="application/rss+xml" href="/feed.xml">
+        <link rel="prev" href="page-1">
+        <link rel="Next" href="https://example.com/blog/page-3">
+        <link rel="preload" as="image" href="/hero.jpg">
+        <link rel="prefetch" href="/blog/page-3">
+        <link rel="modulepreload" href="/app.js">
+        <link rel="stylesheet" href="/site.css">
+        <link rel="preconnect" href="https://cdn.example.net">
+    </head><body></body></html>"#;
+
+    fn all() -> LinkDiscovery {
+        LinkDiscovery::new(&[
+            DiscoverLink::Canonical,
+            DiscoverLink::Hreflang,
+            DiscoverLink::Pagination,
+        ])
+    }
+
+    #[test]
+    fn finds_enabled_relationships() {
+        let hints = all().page_hints(HEAD, PAGE);
+        assert_eq!(
+            hints.canonical.as_deref(),
+            Some("https://example.com/blog/page-2")
+        );
+        assert_eq!(
+            hints.related,
+            [
+                "https://example.com/de/blog/page-2",
+                "https://example.com/blog/page-1",
+                "https://example.com/blog/page-3",
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_resource_hints_and_other_rels() {
+        // Preloads, prefetches and module preloads are assets of the page, not pages
+        let html = r#"<head>
+            <link rel="preload" as="fetch" href="/api/next">
+            <link rel="prefetch" href="/blog/page-3">
+            <link rel="modulepreload" href="/app.js">
+            <link rel="alternate" type="application/atom+xml" href="/atom.xml">
+            <link rel="icon" href="/favicon.ico">
+        </head>"#;
+        let hints = all().page_hints(html, PAGE);
+        assert_eq!(hints.canonical, None);
+        assert!(hints.related.is_empty(), "{:?}", hints.related);
+    }
+
+    #[test]
+    fn only_enabled_kinds_are_returned() {
+        let hints = LinkDiscovery::new(&[DiscoverLink::Pagination]).page_hints(HEAD, PAGE);
+        assert_eq!(hints.canonical, None);
+        assert_eq!(
+            hints.related,
+            [
+                "https://example.com/blog/page-1",
+                "https://example.com/blog/page-3"
+            ]
+        );
+        let hints = LinkDiscovery::default().page_hints(HEAD, PAGE);
+        assert!(hints.canonical.is_none() && hints.related.is_empty());
+    }
+
+    #[test]
+    fn self_canonical_is_not_a_hint() {
+        let html = r#"<head><link rel="canonical" href="https://example.com/blog/"></head>"#;
+        let hints = all().page_hints(html, "https://example.com/blog/");
+        assert_eq!(hints.canonical, None);
+    }
+}
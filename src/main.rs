@@ -1,17 +1,19 @@
+use chrono::{DateTime, Utc};
 use clap::CommandFactory;
 use clap::Parser;
 use colored::*;
-use ctrlc;
 use isahc::HttpClient;
 use isahc::config::SslOption;
 use isahc::config::VersionNegotiation;
 use isahc::{Request, config::RedirectPolicy, prelude::*};
 use rand::Rng;
+use regex::Regex;
 use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
 use serde_xml_rs::from_str;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::fs;
+use std::io::IsTerminal;
 use std::process::exit;
 use std::sync::OnceLock;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -20,12 +22,113 @@ use std::time::{Duration, Instant};
 use tokio::sync::Semaphore;
 use tokio::time::sleep;
 use url::Url;
+mod asset_policy;
+mod compare;
+mod crawl_scope;
+mod crawl_state;
+mod dashboard;
+mod feeds;
+mod histogram;
+mod host_limits;
+mod html_report;
+mod interval;
 mod js_crawler;
+mod junit;
+mod link_discovery;
+mod metrics_push;
+mod output;
+mod prometheus;
+mod rate_limit;
+mod request_log;
+mod results;
+mod robots;
+mod shutdown;
+mod sitemap_media;
+mod thresholds;
+mod timeseries;
+mod url_filter;
+mod url_norm;
+
+use asset_policy::{AssetPolicy, AssetType, ThirdPartyMode};
+use compare::Tolerance;
+use crawl_scope::{CrawlLimits, ScopeMode};
+use crawl_state::{CrawlState, Outcome, Phase};
+use dashboard::Dashboard;
+use histogram::Histogram;
+use host_limits::HostLimits;
+use link_discovery::{DiscoverLink, LinkDiscovery};
+use metrics_push::{MetricsPush, Sink, Tags};
+use output::OutputLevel;
+use rate_limit::RateLimits;
+use request_log::LogFormat;
+use results::RunResults;
+use robots::Robots;
+use thresholds::{Threshold, Verdict};
+use timeseries::TimeSeries;
+use url_filter::UrlFilter;
 
 /// When true, requests force HTTP/1.1 instead of negotiating HTTP/2.
 /// Set once at startup from the resolved config; read on every request.
 static FORCE_HTTP1: AtomicBool = AtomicBool::new(false);
 
+/// When true, URL normalization sorts query parameters. Set once at startup.
+static SORT_QUERY: AtomicBool = AtomicBool::new(false);
+
+/// How `make_request` handles 3xx responses. Set once at startup from the resolved config.
+static REDIRECTS: OnceLock<RedirectSettings> = OnceLock::new();
+
+/// Include/exclude patterns applied to every URL source. Set once at startup.
+static URL_FILTER: OnceLock<UrlFilter> = OnceLock::new();
+
+fn url_filter() -> &'static UrlFilter {
+    URL_FILTER.get_or_init(|| UrlFilter::new(&[], &[]).expect("empty URL filter"))
+}
+
+/// Image and video URLs from sitemap extensions and feed enclosures, keyed by the
+/// normalized URL of the page that owns them. Set by `load_sitemap`; warmed as that
+/// page's assets.
+static SITEMAP_MEDIA: OnceLock<HashMap<String, Vec<String>>> = OnceLock::new();
+
+fn sitemap_media(page_url: &str) -> Vec<String> {
+    SITEMAP_MEDIA
+        .get()
+        .and_then(|media| media.get(&normalize_url(page_url)).cloned())
+        .unwrap_or_default()
+}
+
+/// Asset types to warm and which third-party hosts to fetch them from. Set once at startup.
+static ASSET_POLICY: OnceLock<AssetPolicy> = OnceLock::new();
+
+fn asset_policy() -> &'static AssetPolicy {
+    ASSET_POLICY.get_or_init(|| AssetPolicy::new(&AssetType::ALL, ThirdPartyMode::All, &[]))
+}
+
+/// Stylesheet URL -> the references found in it, so shared CSS is fetched and parsed
+/// once per run whichever page links it.
+static STYLESHEETS: OnceLock<Mutex<HashMap<String, CssReferences>>> = OnceLock::new();
+
+fn stylesheets() -> &'static Mutex<HashMap<String, CssReferences>> {
+    STYLESHEETS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Requests-per-second limits shared by all tasks, in every mode. Set once at startup.
+static RATE_LIMITS: OnceLock<RateLimits> = OnceLock::new();
+
+fn rate_limits() -> &'static RateLimits {
+    RATE_LIMITS.get_or_init(RateLimits::unlimited)
+}
+
+/// Per-host concurrency cap for the `-w` crawl. Set when the crawl starts,
+/// so URL discovery is not slowed down by them.
+static HOST_LIMITS: OnceLock<HostLimits> = OnceLock::new();
+
+/// Crawl progress checkpointed to `--state-file`. Set once at startup when enabled.
+static CRAWL_STATE: OnceLock<CrawlState> = OnceLock::new();
+
+fn crawl_state() -> Option<&'static CrawlState> {
+    CRAWL_STATE.get()
+}
+
 /// Shared HTTP client with unlimited connection pool per host.
 /// isahc's defaults are browser-like (~6 connections per host), which caps real
 /// concurrency well below the requested `--concurrent` level in a load test.
@@ -44,11 +147,49 @@ fn http_client() -> &'static HttpClient {
                     | SslOption::DANGER_ACCEPT_INVALID_HOSTS,
             )
             .redirect_policy(RedirectPolicy::Follow)
+            // Connection timings are only collected for the verbose output level and
+            // the request log
+            .metrics(output::level() == OutputLevel::Verbose || request_log::is_active())
             .build()
             .expect("failed to build shared HttpClient")
     })
 }
 
+/// Redirect handling for page and asset requests.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum RedirectMode {
+    /// Follow every redirect up to the limit
+    Follow,
+    /// Do not follow; the 3xx response is the result
+    None,
+    /// Follow redirects only while they stay on the requested host
+    SameHost,
+}
+
+#[derive(Clone, Copy)]
+struct RedirectSettings {
+    mode: RedirectMode,
+    max: usize,
+}
+
+fn redirect_settings() -> RedirectSettings {
+    *REDIRECTS.get_or_init(|| RedirectSettings {
+        mode: RedirectMode::Follow,
+        max: 10,
+    })
+}
+
+/// Redirect policy for requests that let libcurl follow redirects (the JS crawler's
+/// asset fetches), where the chain is not recorded and same-host cannot be enforced.
+fn isahc_redirect_policy() -> RedirectPolicy {
+    let settings = redirect_settings();
+    match settings.mode {
+        RedirectMode::None => RedirectPolicy::None,
+        RedirectMode::Follow | RedirectMode::SameHost => RedirectPolicy::Limit(settings.max as u32),
+    }
+}
+
 /// The struct to deserialize and hold the items in <url></url>
 /// in the sitemap.xml
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
@@ -60,6 +201,20 @@ struct Urlc {
     changefreq: String,
     #[serde(default = "default_priority")]
     priority: String,
+    /// `<xhtml:link rel="alternate" hreflang="..">` language alternates
+    #[serde(default, rename = "xhtml:link")]
+    alternates: Vec<XhtmlLink>,
+}
+
+/// An `<xhtml:link>` entry inside a sitemap `<url>`.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct XhtmlLink {
+    #[serde(default, rename = "@rel")]
+    rel: String,
+    #[serde(default, rename = "@hreflang")]
+    hreflang: Option<String>,
+    #[serde(rename = "@href")]
+    href: String,
 }
 
 /// The struct to hold the urlset items in the sitemap.xml.
@@ -91,10 +246,91 @@ struct Cli {
     #[arg(short = 'd', long = "delay", default_value_t = 0)]
     delay: u64,
 
-    /// Verbose output
+    /// Verbose output: per-request lines plus response headers, connection timings and
+    /// cache status (same as --output verbose)
     #[arg(short = 'v', long = "verbose")]
     verbose: bool,
 
+    /// Print only setup messages and the final summary (same as --output quiet)
+    #[arg(short = 'q', long = "quiet")]
+    quiet: bool,
+
+    /// Output level: quiet, periodic (a summary line every --progress-interval),
+    /// requests (one line per request, the default) or verbose
+    #[arg(long = "output", value_enum)]
+    output: Option<OutputLevel>,
+
+    /// Seconds between summary lines at the periodic output level (default: 10)
+    #[arg(long = "progress-interval")]
+    progress_interval: Option<u64>,
+
+    /// Serve live Prometheus metrics on this address at /metrics, e.g. 127.0.0.1:9898
+    #[arg(long = "metrics-listen")]
+    metrics_listen: Option<String>,
+
+    /// Push interval aggregates to a metrics sink (repeatable): statsd://host:8125,
+    /// influx+http://host:8086/api/v2/write?org=..&bucket=.. or otlp+http://host:4318
+    #[arg(long = "push-metrics")]
+    push_metrics: Vec<String>,
+
+    /// Seconds between metrics pushes (default: 10)
+    #[arg(long = "push-interval")]
+    push_interval: Option<u64>,
+
+    /// Run ID tag on pushed metrics (default: start time plus a random suffix)
+    #[arg(long = "run-id")]
+    run_id: Option<String>,
+
+    /// Write one line per request (timestamp, URL, page or asset, virtual user,
+    /// status, HTTP version, bytes, timings, cache status, error) to this file
+    #[arg(long = "log-requests")]
+    log_requests: Option<String>,
+
+    /// Format of --log-requests: csv or jsonl (default: csv for .csv paths, else jsonl)
+    #[arg(long = "log-format", value_enum)]
+    log_format: Option<LogFormat>,
+
+    /// Write a self-contained HTML report (charts, status codes, per-URL tables,
+    /// errors, cache hit ratio, run configuration) to this file when the run ends
+    #[arg(long = "report-html")]
+    report_html: Option<String>,
+
+    /// Write a JUnit XML report for CI to this file: one test case per URL, failing on
+    /// a status outside 2xx/3xx and erroring when a request got no response
+    #[arg(long = "report-junit")]
+    report_junit: Option<String>,
+
+    /// Pass/fail condition checked when the run ends (repeatable), e.g.
+    /// 'availability >= 99.5', 'p95 < 800ms', 'error_rate < 1%', 'cache_hit_ratio > 0.9'.
+    /// Prefix the metric with 'interval.' to require it in every --bucket-interval,
+    /// e.g. 'interval.error_rate < 5%'. A failed threshold makes warmer exit with status 4
+    #[arg(long = "threshold")]
+    thresholds: Vec<String>,
+
+    /// Width of the time-series buckets behind the charts, the JSON results and
+    /// interval thresholds, in seconds (default: 1)
+    #[arg(long = "bucket-interval")]
+    bucket_interval: Option<u64>,
+
+    /// Write the run's results (totals, latency percentiles, status codes, errors,
+    /// per-URL numbers) as JSON to this file, for --compare in a later run
+    #[arg(long = "report-json")]
+    report_json: Option<String>,
+
+    /// Compare the run with results saved by --report-json; a regression beyond the
+    /// tolerances makes warmer exit with status 5
+    #[arg(long = "compare")]
+    compare: Option<String>,
+
+    /// --compare: allowed drop in throughput and rise in latency, overall and per
+    /// URL, in percent (default: 10)
+    #[arg(long = "regression-tolerance")]
+    regression_tolerance: Option<f64>,
+
+    /// --compare: allowed rise in error rate, in percentage points (default: 1)
+    #[arg(long = "error-rate-tolerance")]
+    error_rate_tolerance: Option<f64>,
+
     /// Use sitemap mode (default if no URL provided)
     #[arg(short = 's', long = "sitemap")]
     sitemap: bool,
@@ -135,6 +371,154 @@ struct Cli {
     /// include Content-Length, so inline per-request byte counts match siege.
     #[arg(short = 'H', long = "http1")]
     http1: bool,
+
+    /// Redirect handling: follow, none or same-host (default: follow)
+    #[arg(long = "redirects", value_enum)]
+    redirects: Option<RedirectMode>,
+
+    /// Maximum number of redirects followed per request (default: 10)
+    #[arg(long = "max-redirects")]
+    max_redirects: Option<usize>,
+
+    /// Ignore robots.txt Allow/Disallow and Crawl-delay rules in follow-links and JS modes
+    /// (for crawling your own sites)
+    #[arg(long = "ignore-robots")]
+    ignore_robots: bool,
+
+    /// Only warm pages matching this pattern (repeatable). Globs use '*' and match the
+    /// whole path if they start with '/', else the whole URL; prefix 're:' for a regex
+    #[arg(long = "include")]
+    include: Vec<String>,
+
+    /// Skip pages and assets matching this pattern (repeatable, same syntax as --include)
+    #[arg(long = "exclude")]
+    exclude: Vec<String>,
+
+    /// Maximum pages to discover in follow-links and JS modes
+    /// (default: 500 for follow-links, unlimited for JS)
+    #[arg(long = "max-pages")]
+    max_pages: Option<usize>,
+
+    /// Maximum link depth from the start URL in follow-links and JS modes (default: unlimited)
+    #[arg(long = "max-depth")]
+    max_depth: Option<usize>,
+
+    /// Crawl scope: host (exact start host) or subdomains (default: host)
+    #[arg(long = "scope", value_enum)]
+    scope: Option<ScopeMode>,
+
+    /// Additional host the crawlers may follow links to (repeatable)
+    #[arg(long = "allow-host")]
+    allow_hosts: Vec<String>,
+
+    /// Only follow links whose path starts with this prefix (e.g. /blog/)
+    #[arg(long = "path-prefix")]
+    path_prefix: Option<String>,
+
+    /// Extra links to discover, comma-separated: canonical (warm rel=canonical targets
+    /// instead of duplicates), hreflang (language alternates, also from sitemaps) and
+    /// pagination (rel=next/prev)
+    #[arg(long = "discover", value_enum, value_delimiter = ',')]
+    discover: Vec<DiscoverLink>,
+
+    /// RSS or Atom feed whose item links and enclosures are warmed along with the
+    /// sitemap URLs (repeatable)
+    #[arg(long = "feed")]
+    feeds: Vec<String>,
+
+    /// Also warm the RSS/Atom feeds advertised on the start page with
+    /// <link rel="alternate" type="application/rss+xml">
+    #[arg(long = "discover-feeds")]
+    discover_feeds: bool,
+
+    /// Sort query parameters when deduplicating URLs, so ?b=2&a=1 and ?a=1&b=2
+    /// count as the same page
+    #[arg(long = "sort-query")]
+    sort_query: bool,
+
+    /// Asset types to warm, comma-separated: stylesheet, script, image, font, media,
+    /// document (default: all)
+    #[arg(long = "asset-types", value_enum, value_delimiter = ',')]
+    asset_types: Vec<AssetType>,
+
+    /// Assets on other hosts: skip, allowlist (only --cdn-host hosts) or all
+    /// (default: allowlist when --cdn-host is given, else all)
+    #[arg(long = "third-party", value_enum)]
+    third_party: Option<ThirdPartyMode>,
+
+    /// Third-party host whose assets are warmed in allowlist mode, including its
+    /// subdomains (repeatable)
+    #[arg(long = "cdn-host")]
+    cdn_hosts: Vec<String>,
+
+    /// Checkpoint crawl progress to this file so an interrupted crawl can be resumed
+    /// (default with --resume: warmer-state.json)
+    #[arg(long = "state-file")]
+    state_file: Option<String>,
+
+    /// Continue the crawl saved in the state file instead of starting over
+    #[arg(long = "resume")]
+    resume: bool,
+
+    /// Seconds in-flight requests get to finish after Ctrl-C before they are abandoned
+    /// (default: 10). A second Ctrl-C quits immediately.
+    #[arg(long = "grace-period")]
+    grace_period: Option<u64>,
+
+    /// Crawl mode: at most this many requests in flight per host (default: no cap
+    /// beyond -c)
+    #[arg(long = "host-concurrency")]
+    host_concurrency: Option<usize>,
+
+    /// Requests per second to each host, pages and assets together, e.g. 2.5
+    #[arg(long = "host-rps")]
+    host_rps: Option<f64>,
+
+    /// Page requests per second across all users and hosts, e.g. 0.5 or 40
+    #[arg(long = "page-rate")]
+    page_rate: Option<f64>,
+
+    /// Asset requests per second across all users and hosts
+    #[arg(long = "asset-rate")]
+    asset_rate: Option<f64>,
+
+    /// Page requests per second to each host
+    #[arg(long = "host-page-rate")]
+    host_page_rate: Option<f64>,
+
+    /// Asset requests per second to each host
+    #[arg(long = "host-asset-rate")]
+    host_asset_rate: Option<f64>,
+
+    /// Requests a rate limit lets through at once after an idle period (default: 1)
+    #[arg(long = "rate-burst")]
+    rate_burst: Option<f64>,
+
+    /// Show a full-screen live dashboard (rates, latency percentiles, status codes,
+    /// errors, cache hit ratio, progress) instead of one line per request
+    #[arg(long = "dashboard")]
+    dashboard: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Compare two results files written by --report-json, without running a test.
+    /// Exits with status 5 when CURRENT regressed against BASELINE
+    Compare {
+        /// Results of the earlier run
+        baseline: String,
+        /// Results of the run to check
+        current: String,
+        /// Allowed drop in throughput and rise in latency, overall and per URL, in percent
+        #[arg(long = "regression-tolerance", default_value_t = 10.0)]
+        regression_tolerance: f64,
+        /// Allowed rise in error rate, in percentage points
+        #[arg(long = "error-rate-tolerance", default_value_t = 1.0)]
+        error_rate_tolerance: f64,
+    },
 }
 
 /// Configuration loaded from a TOML file (everything except URL).
@@ -181,8 +565,149 @@ struct FileConfig {
     user_agent_list: Vec<String>,
     #[serde(default)]
     http1: Option<bool>,
+    #[serde(default)]
+    redirects: Option<RedirectMode>,
+    #[serde(default, rename = "max_redirects", alias = "max-redirects")]
+    max_redirects: Option<usize>,
+    #[serde(default, rename = "ignore_robots", alias = "ignore-robots")]
+    ignore_robots: Option<bool>,
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+    #[serde(default, rename = "max_pages", alias = "max-pages")]
+    max_pages: Option<usize>,
+    #[serde(default, rename = "max_depth", alias = "max-depth")]
+    max_depth: Option<usize>,
+    #[serde(default)]
+    scope: Option<ScopeMode>,
+    #[serde(
+        default,
+        rename = "allow_hosts",
+        alias = "allow-hosts",
+        alias = "allow-host"
+    )]
+    allow_hosts: Vec<String>,
+    #[serde(default, rename = "path_prefix", alias = "path-prefix")]
+    path_prefix: Option<String>,
+    #[serde(default)]
+    discover: Vec<DiscoverLink>,
+    #[serde(default, alias = "feed")]
+    feeds: Vec<String>,
+    #[serde(default, rename = "discover_feeds", alias = "discover-feeds")]
+    discover_feeds: Option<bool>,
+    #[serde(default, rename = "sort_query", alias = "sort-query")]
+    sort_query: Option<bool>,
+    #[serde(default, rename = "asset_types", alias = "asset-types")]
+    asset_types: Vec<AssetType>,
+    #[serde(default, rename = "third_party", alias = "third-party")]
+    third_party: Option<ThirdPartyMode>,
+    #[serde(default, rename = "cdn_hosts", alias = "cdn-hosts", alias = "cdn-host")]
+    cdn_hosts: Vec<String>,
+    #[serde(default, rename = "state_file", alias = "state-file")]
+    state_file: Option<String>,
+    #[serde(default)]
+    resume: Option<bool>,
+    #[serde(default, rename = "grace_period", alias = "grace-period")]
+    grace_period: Option<u64>,
+    #[serde(default, rename = "host_concurrency", alias = "host-concurrency")]
+    host_concurrency: Option<usize>,
+    #[serde(default, rename = "host_rps", alias = "host-rps")]
+    host_rps: Option<f64>,
+    #[serde(default, rename = "page_rate", alias = "page-rate")]
+    page_rate: Option<f64>,
+    #[serde(default, rename = "asset_rate", alias = "asset-rate")]
+    asset_rate: Option<f64>,
+    #[serde(default, rename = "host_page_rate", alias = "host-page-rate")]
+    host_page_rate: Option<f64>,
+    #[serde(default, rename = "host_asset_rate", alias = "host-asset-rate")]
+    host_asset_rate: Option<f64>,
+    #[serde(default, rename = "rate_burst", alias = "rate-burst")]
+    rate_burst: Option<f64>,
+    #[serde(default)]
+    dashboard: Option<bool>,
+    #[serde(default)]
+    output: Option<OutputLevel>,
+    #[serde(default, rename = "progress_interval", alias = "progress-interval")]
+    progress_interval: Option<u64>,
+    #[serde(default, rename = "metrics_listen", alias = "metrics-listen")]
+    metrics_listen: Option<String>,
+    #[serde(default, rename = "push_metrics", alias = "push-metrics")]
+    push_metrics: Vec<String>,
+    #[serde(default, rename = "push_interval", alias = "push-interval")]
+    push_interval: Option<u64>,
+    #[serde(default, rename = "run_id", alias = "run-id")]
+    run_id: Option<String>,
+    #[serde(default, rename = "log_requests", alias = "log-requests")]
+    log_requests: Option<String>,
+    #[serde(default, rename = "log_format", alias = "log-format")]
+    log_format: Option<LogFormat>,
+    #[serde(default, rename = "report_html", alias = "report-html")]
+    report_html: Option<String>,
+    #[serde(default, rename = "report_junit", alias = "report-junit")]
+    report_junit: Option<String>,
+    #[serde(default)]
+    thresholds: Vec<String>,
+    #[serde(default, rename = "bucket_interval", alias = "bucket-interval")]
+    bucket_interval: Option<u64>,
+    #[serde(default, rename = "report_json", alias = "report-json")]
+    report_json: Option<String>,
+    #[serde(default)]
+    compare: Option<String>,
+    #[serde(
+        default,
+        rename = "regression_tolerance",
+        alias = "regression-tolerance"
+    )]
+    regression_tolerance: Option<f64>,
+    #[serde(
+        default,
+        rename = "error_rate_tolerance",
+        alias = "error-rate-tolerance"
+    )]
+    error_rate_tolerance: Option<f64>,
+}
+
+/// How a run ended, mapped to the process exit code so CI can gate on warmer.
+/// Unexpected runtime errors exit with 1 and an interrupted run with 130.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RunStatus {
+    Passed,
+    /// Invalid options or config file
+    ConfigError,
+    /// Nothing to warm could be found, or no request got a response
+    Unreachable,
+    /// At least one --threshold failed
+    ThresholdsFailed,
+    /// The run regressed against the --compare baseline
+    Regressed,
+}
+
+impl RunStatus {
+    fn exit_code(self) -> i32 {
+        match self {
+            Self::Passed => 0,
+            Self::ConfigError => 2,
+            Self::Unreachable => 3,
+            Self::ThresholdsFailed => 4,
+            Self::Regressed => 5,
+        }
+    }
+}
+
+/// An invalid option value, or a file or address named by an option that can't be used,
+/// reported with `RunStatus::ConfigError`'s exit code.
+#[derive(Debug)]
+struct ConfigError(String);
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
 }
 
+impl std::error::Error for ConfigError {}
+
 /// Effective configuration after merging CLI and file. Single source of truth for runtime.
 #[derive(Clone)]
 struct ResolvedConfig {
@@ -203,17 +728,88 @@ struct ResolvedConfig {
     user_agent_list: Vec<String>,
     anonymize: bool,
     http1: bool,
+    redirects: RedirectMode,
+    max_redirects: usize,
+    ignore_robots: bool,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    max_pages: Option<usize>,
+    max_depth: Option<usize>,
+    scope: ScopeMode,
+    allow_hosts: Vec<String>,
+    path_prefix: Option<String>,
+    discover: Vec<DiscoverLink>,
+    feeds: Vec<String>,
+    discover_feeds: bool,
+    sort_query: bool,
+    asset_types: Vec<AssetType>,
+    third_party: ThirdPartyMode,
+    cdn_hosts: Vec<String>,
+    state_file: Option<String>,
+    resume: bool,
+    grace_period: u64,
+    host_concurrency: Option<usize>,
+    host_rps: Option<f64>,
+    page_rate: Option<f64>,
+    asset_rate: Option<f64>,
+    host_page_rate: Option<f64>,
+    host_asset_rate: Option<f64>,
+    rate_burst: f64,
+    dashboard: bool,
+    output: OutputLevel,
+    progress_interval: u64,
+    metrics_listen: Option<String>,
+    push_metrics: Vec<String>,
+    push_interval: u64,
+    run_id: String,
+    log_requests: Option<String>,
+    log_format: Option<LogFormat>,
+    report_html: Option<String>,
+    report_junit: Option<String>,
+    thresholds: Vec<String>,
+    bucket_interval: u64,
+    report_json: Option<String>,
+    compare: Option<String>,
+    tolerance: Tolerance,
 }
 
 /// Merges CLI and file config. **CLI takes precedence for all options** except user-agent
 /// (user_agent and user_agent_list), which are long and stay config-only / config wins.
 fn resolve_config(cli: Cli, file: &FileConfig) -> ResolvedConfig {
+    // --output wins over the -q / -v shorthands
+    let output = cli
+        .output
+        .or_else(|| cli.quiet.then_some(OutputLevel::Quiet))
+        .or_else(|| cli.verbose.then_some(OutputLevel::Verbose))
+        .or(file.output)
+        .or_else(|| {
+            file.verbose
+                .unwrap_or(false)
+                .then_some(OutputLevel::Verbose)
+        })
+        .unwrap_or(OutputLevel::Requests);
+
+    // Listing CDN hosts opts in to the allowlist; otherwise every host is warmed
+    let cdn_hosts = if cli.cdn_hosts.is_empty() {
+        file.cdn_hosts.clone()
+    } else {
+        cli.cdn_hosts
+    };
+    let third_party = cli
+        .third_party
+        .or(file.third_party)
+        .unwrap_or(if cdn_hosts.is_empty() {
+            ThirdPartyMode::All
+        } else {
+            ThirdPartyMode::Allowlist
+        });
+
     ResolvedConfig {
         concurrent: cli.concurrent,
         time: cli.time.or_else(|| file.time.clone()),
         repetitions: cli.repetitions.or(file.repetitions),
         delay: cli.delay,
-        verbose: cli.verbose,
+        verbose: output == OutputLevel::Verbose,
         sitemap: cli.sitemap,
         internet: cli.internet,
         no_assets: cli.no_assets,
@@ -229,42 +825,406 @@ fn resolve_config(cli: Cli, file: &FileConfig) -> ResolvedConfig {
         },
         anonymize: cli.anonymize,
         http1: cli.http1 || file.http1.unwrap_or(false),
+        redirects: cli
+            .redirects
+            .or(file.redirects)
+            .unwrap_or(RedirectMode::Follow),
+        max_redirects: cli.max_redirects.or(file.max_redirects).unwrap_or(10),
+        ignore_robots: cli.ignore_robots || file.ignore_robots.unwrap_or(false),
+        include: if cli.include.is_empty() {
+            file.include.clone()
+        } else {
+            cli.include
+        },
+        exclude: if cli.exclude.is_empty() {
+            file.exclude.clone()
+        } else {
+            cli.exclude
+        },
+        max_pages: cli.max_pages.or(file.max_pages),
+        max_depth: cli.max_depth.or(file.max_depth),
+        scope: cli.scope.or(file.scope).unwrap_or(ScopeMode::Host),
+        allow_hosts: if cli.allow_hosts.is_empty() {
+            file.allow_hosts.clone()
+        } else {
+            cli.allow_hosts
+        },
+        path_prefix: cli.path_prefix.or_else(|| file.path_prefix.clone()),
+        discover: if cli.discover.is_empty() {
+            file.discover.clone()
+        } else {
+            cli.discover
+        },
+        feeds: if cli.feeds.is_empty() {
+            file.feeds.clone()
+        } else {
+            cli.feeds
+        },
+        discover_feeds: cli.discover_feeds || file.discover_feeds.unwrap_or(false),
+        sort_query: cli.sort_query || file.sort_query.unwrap_or(false),
+        asset_types: if !cli.asset_types.is_empty() {
+            cli.asset_types
+        } else if !file.asset_types.is_empty() {
+            file.asset_types.clone()
+        } else {
+            AssetType::ALL.to_vec()
+        },
+        third_party,
+        cdn_hosts,
+        state_file: cli.state_file.or_else(|| file.state_file.clone()),
+        resume: cli.resume || file.resume.unwrap_or(false),
+        grace_period: cli.grace_period.or(file.grace_period).unwrap_or(10),
+        host_concurrency: cli.host_concurrency.or(file.host_concurrency),
+        host_rps: cli.host_rps.or(file.host_rps),
+        page_rate: cli.page_rate.or(file.page_rate),
+        asset_rate: cli.asset_rate.or(file.asset_rate),
+        host_page_rate: cli.host_page_rate.or(file.host_page_rate),
+        host_asset_rate: cli.host_asset_rate.or(file.host_asset_rate),
+        rate_burst: cli.rate_burst.or(file.rate_burst).unwrap_or(1.0),
+        dashboard: cli.dashboard || file.dashboard.unwrap_or(false),
+        output,
+        progress_interval: cli
+            .progress_interval
+            .or(file.progress_interval)
+            .unwrap_or(10),
+        metrics_listen: cli.metrics_listen.or_else(|| file.metrics_listen.clone()),
+        push_metrics: if cli.push_metrics.is_empty() {
+            file.push_metrics.clone()
+        } else {
+            cli.push_metrics
+        },
+        push_interval: cli.push_interval.or(file.push_interval).unwrap_or(10),
+        run_id: cli
+            .run_id
+            .or_else(|| file.run_id.clone())
+            .unwrap_or_else(metrics_push::default_run_id),
+        log_requests: cli.log_requests.or_else(|| file.log_requests.clone()),
+        log_format: cli.log_format.or(file.log_format),
+        report_html: cli.report_html.or_else(|| file.report_html.clone()),
+        report_junit: cli.report_junit.or_else(|| file.report_junit.clone()),
+        thresholds: if cli.thresholds.is_empty() {
+            file.thresholds.clone()
+        } else {
+            cli.thresholds
+        },
+        bucket_interval: cli.bucket_interval.or(file.bucket_interval).unwrap_or(1),
+        report_json: cli.report_json.or_else(|| file.report_json.clone()),
+        compare: cli.compare.or_else(|| file.compare.clone()),
+        tolerance: Tolerance {
+            percent: cli
+                .regression_tolerance
+                .or(file.regression_tolerance)
+                .unwrap_or(10.0),
+            error_rate: cli
+                .error_rate_tolerance
+                .or(file.error_rate_tolerance)
+                .unwrap_or(1.0),
+        },
+    }
+}
+
+/// Category of a request that failed before any response arrived.
+/// Derived from the isahc error kind so the summary can tell an origin that is
+/// falling over (connection failures, resets, timeouts) apart from our own network
+/// failing (DNS).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum ErrorCategory {
+    NameResolution,
+    ConnectionFailed,
+    ConnectionReset,
+    Timeout,
+    Tls,
+    TooManyRedirects,
+    Protocol,
+    InvalidRequest,
+    Io,
+    Other,
+}
+
+impl ErrorCategory {
+    fn from_isahc(error: &isahc::Error) -> Self {
+        use isahc::error::ErrorKind;
+        match error.kind() {
+            ErrorKind::NameResolution => Self::NameResolution,
+            ErrorKind::ConnectionFailed => Self::ConnectionFailed,
+            ErrorKind::Timeout => Self::Timeout,
+            ErrorKind::BadServerCertificate
+            | ErrorKind::BadClientCertificate
+            | ErrorKind::TlsEngine => Self::Tls,
+            ErrorKind::TooManyRedirects => Self::TooManyRedirects,
+            ErrorKind::ProtocolViolation | ErrorKind::InvalidContentEncoding => Self::Protocol,
+            ErrorKind::InvalidRequest
+            | ErrorKind::InvalidCredentials
+            | ErrorKind::RequestBodyNotRewindable => Self::InvalidRequest,
+            // libcurl reports a reset as a generic send/receive failure; the message
+            // is the only place the distinction survives.
+            ErrorKind::Io if error.to_string().to_lowercase().contains("reset") => {
+                Self::ConnectionReset
+            }
+            ErrorKind::Io => Self::Io,
+            _ => Self::Other,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::NameResolution => "DNS failure",
+            Self::ConnectionFailed => "Connection failed",
+            Self::ConnectionReset => "Connection reset",
+            Self::Timeout => "Timeout",
+            Self::Tls => "TLS error",
+            Self::TooManyRedirects => "Too many redirects",
+            Self::Protocol => "Protocol error",
+            Self::InvalidRequest => "Invalid request",
+            Self::Io => "I/O error",
+            Self::Other => "Other error",
+        }
+    }
+
+    /// Short lowercase name for metric tags and labels.
+    fn tag(&self) -> &'static str {
+        match self {
+            Self::NameResolution => "dns",
+            Self::ConnectionFailed => "connection_failed",
+            Self::ConnectionReset => "connection_reset",
+            Self::Timeout => "timeout",
+            Self::Tls => "tls",
+            Self::TooManyRedirects => "too_many_redirects",
+            Self::Protocol => "protocol",
+            Self::InvalidRequest => "invalid_request",
+            Self::Io => "io",
+            Self::Other => "other",
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// A classified request failure together with the underlying error message.
+#[derive(Clone, Debug)]
+struct RequestError {
+    category: ErrorCategory,
+    message: String,
+}
+
+impl RequestError {
+    fn from_isahc(error: &isahc::Error) -> Self {
+        Self {
+            category: ErrorCategory::from_isahc(error),
+            message: error.to_string(),
+        }
+    }
+
+    fn invalid_request(error: &isahc::http::Error) -> Self {
+        Self {
+            category: ErrorCategory::InvalidRequest,
+            message: error.to_string(),
+        }
+    }
+}
+
+/// One redirect response seen while resolving a request.
+#[derive(Clone, Debug)]
+struct RedirectHop {
+    status_code: u16,
+    location: String,
+}
+
+/// Connection timings of a response in ms, cumulative from the request start.
+#[derive(Clone, Copy, Debug)]
+struct Timings {
+    dns: f64,
+    connect: f64,
+    tls: f64,
+    first_byte: f64,
+}
+
+impl Timings {
+    fn of(metrics: &isahc::Metrics) -> Self {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let dns = metrics.name_lookup_time();
+        let connect = dns + metrics.connect_time();
+        let tls = connect + metrics.secure_connect_time();
+        Self {
+            dns: ms(dns),
+            connect: ms(connect),
+            tls: ms(tls),
+            first_byte: ms(metrics.transfer_start_time()),
+        }
+    }
+}
+
+/// Outcome of a single request as returned by `make_request`.
+struct RequestResult {
+    /// The URL that was requested, before any redirects.
+    url: String,
+    started_at: DateTime<Utc>,
+    status_code: u16,
+    response_time: f64,
+    data_size: u64,
+    /// Response body, only read when the caller asked for it and the status was 200.
+    body: Option<String>,
+    http_version: String,
+    /// Set when no response arrived; `status_code` is 0 in that case.
+    error: Option<RequestError>,
+    /// Redirects seen in order; the last hop's location is where we ended up.
+    redirects: Vec<RedirectHop>,
+    /// Why a redirect was not followed, if the chain ended on a 3xx.
+    redirect_stopped: Option<&'static str>,
+    /// Whether a CDN or proxy answered from cache; None without cache status headers.
+    cache_hit: Option<bool>,
+    /// True for page requests, false for assets.
+    is_main_url: bool,
+    /// Connection timings of the final response, when the client collects them.
+    timings: Option<Timings>,
+}
+
+impl RequestResult {
+    fn failed(url: &str, response_time: f64, error: RequestError) -> Self {
+        Self {
+            url: url.to_string(),
+            started_at: Utc::now() - chrono::Duration::milliseconds(response_time as i64),
+            status_code: 0,
+            response_time,
+            data_size: 0,
+            body: None,
+            http_version: "HTTP/1.1".to_string(),
+            error: Some(error),
+            redirects: Vec::new(),
+            redirect_stopped: None,
+            cache_hit: None,
+            is_main_url: false,
+            timings: None,
+        }
+    }
+}
+
+/// A requested URL that did not resolve to itself, as shown in the summary.
+#[derive(Clone)]
+struct RedirectRecord {
+    hops: Vec<RedirectHop>,
+    stopped: Option<&'static str>,
+}
+
+/// Numbers for a single requested URL, for the per-URL tables of the reports.
+#[derive(Clone, Default)]
+struct UrlStats {
+    is_main_url: bool,
+    requests: usize,
+    failed: usize,
+    bytes: u64,
+    latency: Histogram,
+    status_codes: BTreeMap<u16, usize>,
+    /// Most recent failure of a request that got no response
+    last_error: Option<RequestError>,
+}
+
+impl UrlStats {
+    fn avg_response_time(&self) -> f64 {
+        self.latency.avg().unwrap_or(0.0)
+    }
+
+    fn max_response_time(&self) -> f64 {
+        self.latency.max().unwrap_or(0.0)
     }
 }
 
+/// Count a finished request in the run's stats, the metrics exporter and the request
+/// log. Only the stats are updated under the lock; the exporter and log entries are
+/// built before taking it.
+fn record_result(stats: &Mutex<Stats>, result: &RequestResult) {
+    prometheus::observe(result);
+    request_log::record(result);
+    stats.lock().unwrap().add_transaction(result);
+}
+
 /// Performance statistics tracking
 #[derive(Clone, Default)]
 struct Stats {
     transactions: usize,
     successful_transactions: usize,
     failed_transactions: usize,
-    response_times: Vec<f64>,
+    /// Response times of every request, in ms.
+    latency: Histogram,
     data_transferred: u64,
     start_time: Option<Instant>,
     end_time: Option<Instant>,
     status_codes: HashMap<u16, usize>,
+    errors: HashMap<ErrorCategory, usize>,
+    /// Requested URL -> redirect chain, for every URL that redirected.
+    redirects: HashMap<String, RedirectRecord>,
+    /// Responses with a cache status header, split by hit or miss.
+    cache_hits: usize,
+    cache_misses: usize,
+    /// Wall-clock start of the run, for reports.
+    started_at: DateTime<Utc>,
+    /// Requests, failures, bytes and latency per --bucket-interval of the run.
+    timeline: TimeSeries,
+    /// Requested URL -> its own numbers.
+    urls: HashMap<String, UrlStats>,
 }
 
 impl Stats {
-    fn new() -> Self {
+    fn new(bucket_width: Duration) -> Self {
         Self {
             start_time: Some(Instant::now()),
+            started_at: Utc::now(),
+            timeline: TimeSeries::new(bucket_width),
             ..Default::default()
         }
     }
 
-    fn add_transaction(&mut self, response_time: f64, data_size: u64, status_code: u16) {
+    fn add_transaction(&mut self, result: &RequestResult) {
         self.transactions += 1;
-        self.response_times.push(response_time);
-        self.data_transferred += data_size;
+        self.latency.record(result.response_time);
+        self.data_transferred += result.data_size;
 
-        if status_code < 400 {
+        if let Some(ref error) = result.error {
+            self.failed_transactions += 1;
+            *self.errors.entry(error.category).or_insert(0) += 1;
+        } else if result.status_code < 400 {
             self.successful_transactions += 1;
         } else {
             self.failed_transactions += 1;
         }
 
-        *self.status_codes.entry(status_code).or_insert(0) += 1;
+        *self.status_codes.entry(result.status_code).or_insert(0) += 1;
+
+        match result.cache_hit {
+            Some(true) => self.cache_hits += 1,
+            Some(false) => self.cache_misses += 1,
+            None => {}
+        }
+
+        if let Some(start) = self.start_time {
+            self.timeline.record(start.elapsed(), result);
+        }
+        let url = self.urls.entry(result.url.clone()).or_default();
+        url.is_main_url |= result.is_main_url;
+        url.requests += 1;
+        url.bytes += result.data_size;
+        url.latency.record(result.response_time);
+        *url.status_codes.entry(result.status_code).or_insert(0) += 1;
+        if result.error.is_some() || result.status_code >= 400 {
+            url.failed += 1;
+        }
+        if let Some(ref error) = result.error {
+            url.last_error = Some(error.clone());
+        }
+
+        if !result.redirects.is_empty() && !self.redirects.contains_key(&result.url) {
+            self.redirects.insert(
+                result.url.clone(),
+                RedirectRecord {
+                    hops: result.redirects.clone(),
+                    stopped: result.redirect_stopped,
+                },
+            );
+        }
     }
 
     fn finish(&mut self) {
@@ -282,11 +1242,7 @@ impl Stats {
     }
 
     fn avg_response_time(&self) -> f64 {
-        if self.response_times.is_empty() {
-            0.0
-        } else {
-            self.response_times.iter().sum::<f64>() / self.response_times.len() as f64
-        }
+        self.latency.avg().unwrap_or(0.0)
     }
 
     fn transaction_rate(&self) -> f64 {
@@ -308,7 +1264,7 @@ impl Stats {
     }
 
     fn concurrency(&self) -> f64 {
-        if self.response_times.is_empty() {
+        if self.latency.count() == 0 {
             0.0
         } else {
             self.avg_response_time() * self.transaction_rate() / 1000.0
@@ -384,16 +1340,20 @@ fn print_header(concurrent: usize, _url: &str) {
 }
 
 /// Print transaction details with optional highlighting for main URLs
+#[allow(clippy::too_many_arguments)]
 fn print_transaction(
     status_code: u16,
     response_time: f64,
     data_size: u64,
     method: &str,
     path: &str,
-    _verbose: bool,
+    details: Option<&str>,
     is_main_url: bool,
     http_version: &str,
 ) {
+    if !output::shows_requests() {
+        return;
+    }
     let status_colored = color_status_code(status_code);
     let response_time_str = format_response_time(response_time);
     let data_size_str = format_data_size(data_size);
@@ -415,6 +1375,11 @@ fn print_transaction(
             http_version, status_colored, response_time_str, data_size_str, method, path
         );
     }
+    if let Some(details) = details {
+        for line in details.lines() {
+            println!("      {}", line);
+        }
+    }
 }
 
 /// Print final statistics
@@ -441,19 +1406,49 @@ fn print_statistics(stats: &Stats) {
     );
     println!("Failed transactions:\t{:8}", stats.failed_transactions);
 
-    if let Some(&max_time) = stats
-        .response_times
-        .iter()
-        .max_by(|a, b| a.partial_cmp(b).unwrap())
-    {
+    let filtered = url_filter().filtered_count();
+    if filtered > 0 {
+        println!("Filtered URLs:\t\t{:8}", filtered);
+    }
+    let third_party = asset_policy().third_party_skipped();
+    if third_party > 0 {
+        println!("Third-party assets skipped:{:6}", third_party);
+    }
+
+    if !stats.errors.is_empty() {
+        let mut errors: Vec<(&ErrorCategory, &usize)> = stats.errors.iter().collect();
+        errors.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        println!("Request errors:");
+        for (category, count) in errors {
+            println!("  {:<22}{:8}", format!("{}:", category), count);
+        }
+    }
+
+    if !stats.redirects.is_empty() {
+        let mut redirected: Vec<(&String, &RedirectRecord)> = stats.redirects.iter().collect();
+        redirected.sort_by(|a, b| a.0.cmp(b.0));
+        println!("Redirected URLs:\t{:8}", redirected.len());
+        for (url, record) in redirected {
+            let mut line = url.to_string();
+            for hop in &record.hops {
+                line.push_str(&format!(
+                    " -> [{}] {}",
+                    color_status_code(hop.status_code),
+                    hop.location
+                ));
+            }
+            if let Some(reason) = record.stopped {
+                line.push_str(&format!(" ({})", reason));
+            }
+            println!("  {}", line);
+        }
+    }
+
+    if let Some(max_time) = stats.latency.max() {
         println!("Longest transaction:\t{:8.2} ms", max_time);
     }
 
-    if let Some(&min_time) = stats
-        .response_times
-        .iter()
-        .min_by(|a, b| a.partial_cmp(b).unwrap())
-    {
+    if let Some(min_time) = stats.latency.min() {
         println!("Shortest transaction:\t{:8.2} ms", min_time);
     }
 
@@ -515,7 +1510,12 @@ async fn find_sitemap_url_from_robots(
     for line in robots_content.lines() {
         let line = line.trim();
         if line.to_lowercase().starts_with("sitemap:") {
-            let sitemap_url = line.splitn(2, ':').nth(1).unwrap_or("").trim().to_string();
+            let sitemap_url = line
+                .split_once(':')
+                .map(|(_, value)| value)
+                .unwrap_or("")
+                .trim()
+                .to_string();
             if !sitemap_url.is_empty() {
                 sitemap_urls.push(sitemap_url);
             }
@@ -557,10 +1557,88 @@ async fn parse_sitemap_index(content: &str) -> Result<Vec<String>, Box<dyn std::
     }
 }
 
-/// Load URLs from all sitemaps
+/// Fetch a small text resource (feed or page) with the run's User-Agent.
+/// Returns `None` on request errors and non-2xx responses.
+async fn fetch_text(url: &str, user_agent: &str) -> Option<String> {
+    let mut builder = Request::get(url).header("User-Agent", user_agent);
+    if FORCE_HTTP1.load(Ordering::Relaxed) {
+        builder = builder.version_negotiation(VersionNegotiation::http11());
+    }
+    let request = builder.body(()).ok()?;
+    match http_client().send_async(request).await {
+        Ok(mut response) if response.status().is_success() => response.text().await.ok(),
+        Ok(response) => {
+            println!("{} returned status: {}", url, response.status());
+            None
+        }
+        Err(e) => {
+            println!("Error fetching {}: {}", url, e);
+            None
+        }
+    }
+}
+
+/// Load item links from RSS/Atom feeds: the given feed URLs plus, with `discover`,
+/// feeds advertised on the start page. Enclosures allowed by the asset policy are
+/// added to `media` under their item's page.
+async fn load_feeds(
+    base_url: &str,
+    feed_urls: &[String],
+    discover: bool,
+    user_agent: &str,
+    media: &mut HashMap<String, Vec<String>>,
+) -> Vec<String> {
+    let mut feed_urls = feed_urls.to_vec();
+    if discover && let Some(html) = fetch_text(base_url, user_agent).await {
+        let found = feeds::discover(&html, base_url);
+        println!("Discovered {} feeds on {}", found.len(), base_url);
+        feed_urls.extend(found);
+    }
+    feed_urls.sort();
+    feed_urls.dedup();
+
+    let mut page_urls = Vec::new();
+    for feed_url in feed_urls {
+        println!("Processing feed: {}", feed_url);
+        let Some(content) = fetch_text(&feed_url, user_agent).await else {
+            continue;
+        };
+        let items = feeds::parse(&content, &feed_url);
+        let mut enclosures = 0;
+        for item in &items {
+            let Some(ref link) = item.link else {
+                continue;
+            };
+            let allowed: Vec<String> = item
+                .enclosures
+                .iter()
+                .filter(|(_, kind)| asset_policy().allows_listed(*kind))
+                .map(|(asset, _)| asset.clone())
+                .collect();
+            enclosures += allowed.len();
+            media
+                .entry(normalize_url(link))
+                .or_default()
+                .extend(allowed);
+            page_urls.push(link.clone());
+        }
+        println!(
+            "Found {} items in feed ({} enclosures)",
+            items.len(),
+            enclosures
+        );
+    }
+    page_urls
+}
+
+/// Load URLs from all sitemaps, plus their hreflang alternates when enabled, and from
+/// any RSS/Atom feeds
 async fn load_sitemap(
     base_url: &str,
     user_agent_mode: Arc<UserAgentMode>,
+    discovery: LinkDiscovery,
+    feed_urls: &[String],
+    discover_feeds: bool,
 ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
     let user_agent = get_user_agent(&user_agent_mode);
 
@@ -570,6 +1648,7 @@ async fn load_sitemap(
     let mut sitemap_urls_to_process = initial_candidates;
     let mut tried_urls: std::collections::HashSet<String> = std::collections::HashSet::new();
     let mut all_page_urls = Vec::new();
+    let mut media: HashMap<String, Vec<String>> = HashMap::new();
     let mut any_sitemap_found = false;
 
     while !sitemap_urls_to_process.is_empty() {
@@ -643,10 +1722,10 @@ async fn load_sitemap(
         }
 
         // Remove XML declaration if present
-        if content.starts_with("<?xml") {
-            if let Some(end) = content.find("?>") {
-                content = content[end + 2..].trim_start().to_string();
-            }
+        if content.starts_with("<?xml")
+            && let Some(end) = content.find("?>")
+        {
+            content = content[end + 2..].trim_start().to_string();
         }
 
         // Try to parse as sitemap index first
@@ -664,8 +1743,49 @@ async fn load_sitemap(
                 match from_str::<UrlSet>(&content) {
                     Ok(urlset) => {
                         // Extract URLs from this sitemap
-                        let mut urls: Vec<String> = urlset.url.into_iter().map(|u| u.loc).collect();
-                        println!("Found {} URLs in sitemap", urls.len());
+                        let mut urls = Vec::new();
+                        let mut alternates = 0;
+                        let mut media_count = 0;
+                        for page_media in sitemap_media::parse(&content) {
+                            let allowed: Vec<String> = page_media
+                                .assets
+                                .into_iter()
+                                .filter(|(_, kind)| asset_policy().allows_listed(*kind))
+                                .map(|(asset, _)| asset)
+                                .collect();
+                            media_count += allowed.len();
+                            media
+                                .entry(normalize_url(&page_media.page))
+                                .or_default()
+                                .extend(allowed);
+                        }
+                        for entry in urlset.url {
+                            if discovery.hreflang {
+                                for link in entry.alternates {
+                                    if link.rel == "alternate" && link.hreflang.is_some() {
+                                        urls.push(link.href);
+                                        alternates += 1;
+                                    }
+                                }
+                            }
+                            urls.push(entry.loc);
+                        }
+                        let mut extras = Vec::new();
+                        if alternates > 0 {
+                            extras.push(format!("{} hreflang alternates", alternates));
+                        }
+                        if media_count > 0 {
+                            extras.push(format!("{} image/video assets", media_count));
+                        }
+                        if extras.is_empty() {
+                            println!("Found {} URLs in sitemap", urls.len());
+                        } else {
+                            println!(
+                                "Found {} URLs in sitemap ({})",
+                                urls.len(),
+                                extras.join(", ")
+                            );
+                        }
                         all_page_urls.append(&mut urls);
                     }
                     Err(e) => {
@@ -683,24 +1803,42 @@ async fn load_sitemap(
         }
     }
 
-    // If no sitemap was successfully processed, return an error
-    if !any_sitemap_found {
+    // Feeds often carry fresher URLs than the sitemaps
+    let feed_page_urls =
+        load_feeds(base_url, feed_urls, discover_feeds, &user_agent, &mut media).await;
+
+    // If no sitemap was successfully processed and no feed had items, return an error
+    if !any_sitemap_found && feed_page_urls.is_empty() {
         return Err("No valid sitemaps found".into());
     }
+    all_page_urls.extend(feed_page_urls);
 
-    // Deduplicate URLs from all sitemaps
+    // Deduplicate URLs from all sitemaps on their normalized form, but request them as
+    // listed: the normalized form may not be the CDN's cache key
     all_page_urls.sort();
-    all_page_urls.dedup();
+    let mut seen = HashSet::new();
+    all_page_urls.retain(|u| seen.insert(normalize_url(u)));
 
     println!(
         "Total unique URLs found across all sitemaps: {}",
         all_page_urls.len()
     );
 
+    let before_filter = all_page_urls.len();
+    all_page_urls.retain(|u| url_filter().allows(u));
+    if all_page_urls.len() < before_filter {
+        println!(
+            "Filtered out {} URLs by include/exclude patterns, {} left",
+            before_filter - all_page_urls.len(),
+            all_page_urls.len()
+        );
+    }
+
     if all_page_urls.is_empty() {
         return Err("No URLs found in any sitemap".into());
     }
 
+    let _ = SITEMAP_MEDIA.set(media);
     Ok(all_page_urls)
 }
 
@@ -711,39 +1849,67 @@ async fn follow_links_from_url(
     concurrency: usize,
     stats: Arc<Mutex<Stats>>,
     user_agent_mode: Arc<UserAgentMode>,
+    robots: Arc<Robots>,
+    limits: Arc<CrawlLimits>,
+    discovery: LinkDiscovery,
 ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
     println!("Follow-links mode: Starting to crawl from {}", start_url);
 
-    let base_url = Arc::new(if let Ok(parsed) = Url::parse(start_url) {
-        let host = parsed.host_str().unwrap_or_default();
-        if host.is_empty() {
-            start_url.to_string()
-        } else {
-            format!("{}://{}", parsed.scheme(), host)
-        }
-    } else {
-        start_url.to_string()
-    });
+    if !robots.is_allowed(start_url) {
+        return Err(format!(
+            "{} is disallowed by robots.txt (use --ignore-robots for your own sites)",
+            start_url
+        )
+        .into());
+    }
 
-    // Cache: URL -> outgoing same-domain links found on that page.
+    // Cache: URL -> outgoing in-scope links found on that page.
     // Populated on first fetch; avoids re-fetching a page just to discover its links.
     let link_cache: Arc<Mutex<HashMap<String, Vec<String>>>> = Arc::new(Mutex::new(HashMap::new()));
 
-    // All URLs we have seen (or queued). Prevents duplicate frontier entries.
-    let visited: Arc<Mutex<std::collections::HashSet<String>>> =
-        Arc::new(Mutex::new(std::collections::HashSet::new()));
-    visited.lock().unwrap().insert(start_url.to_string());
+    // Normalized URL -> the URL as first seen, for every URL fetched or queued. Prevents
+    // duplicate frontier entries; pages are requested as they were linked.
+    let visited: Arc<Mutex<HashMap<String, String>>> = Arc::new(Mutex::new(HashMap::new()));
+    visited
+        .lock()
+        .unwrap()
+        .insert(normalize_url(start_url), start_url.to_string());
 
-    let sem = Arc::new(Semaphore::new(concurrency));
-    let max_urls = 500;
+    // Pages an interrupted run already fetched, and the frontier it left queued (--resume)
+    let (resumed, pending) = crawl_state()
+        .map(|s| s.resume_point(Phase::Discovery))
+        .unwrap_or_default();
 
-    let mut frontier = vec![start_url.to_string()];
+    // Pages whose rel=canonical points at another in-scope URL; crawled for links,
+    // but only the canonical URL is warmed.
+    let non_canonical: Arc<Mutex<HashSet<String>>> = Arc::new(Mutex::new(HashSet::new()));
 
-    while !frontier.is_empty() {
-        if visited.lock().unwrap().len() >= max_urls {
-            break;
+    // Links robots.txt told us not to fetch, reported once at the end.
+    let mut disallowed: std::collections::HashSet<String> = std::collections::HashSet::new();
+
+    let sem = Arc::new(Semaphore::new(concurrency));
+    let mut page_limit_hit = false;
+    let mut depth_limited = 0;
+
+    // Frontier entries carry their link depth (the start URL is depth 0)
+    let mut frontier = if resumed.is_empty() && pending.is_empty() {
+        if let Some(state) = crawl_state() {
+            state.queue(Phase::Discovery, start_url, 0);
         }
+        vec![(start_url.to_string(), 0)]
+    } else {
+        let mut vis = visited.lock().unwrap();
+        vis.extend(resumed.into_iter().map(|url| (normalize_url(&url), url)));
+        vis.extend(
+            pending
+                .iter()
+                .map(|(url, _)| (normalize_url(url), url.clone())),
+        );
+        pending
+    };
 
+    // On Ctrl-C, stop after the current wave; unfetched pages stay queued in the state file
+    while !frontier.is_empty() && !shutdown::requested() {
         let batch = std::mem::take(&mut frontier);
         println!(
             "Processing {} URLs (total discovered: {})",
@@ -753,91 +1919,172 @@ async fn follow_links_from_url(
 
         let mut handles = vec![];
 
-        for url in batch {
+        for (url, depth) in batch {
             let sem = sem.clone();
             let link_cache = link_cache.clone();
-            let base_url = base_url.clone();
+            let non_canonical = non_canonical.clone();
+            let limits = limits.clone();
             let stats = stats.clone();
             let ua = user_agent_mode.clone();
+            let robots = robots.clone();
 
-            handles.push(tokio::spawn(async move {
-                let _permit = sem.acquire_owned().await.unwrap();
-
-                // Return cached links without re-fetching the page
-                if let Some(cached) = link_cache.lock().unwrap().get(&url).cloned() {
-                    return cached;
-                }
-
-                // Fetch page
-                let (status, time, size, html, _) =
-                    make_request(&url, false, true, ua.clone(), true).await;
-                stats.lock().unwrap().add_transaction(time, size, status);
-
-                let Some(html_content) = html else {
-                    link_cache.lock().unwrap().insert(url.clone(), vec![]);
-                    return vec![];
-                };
+            handles.push((
+                url.clone(),
+                depth,
+                tokio::spawn(async move {
+                    let _permit = sem.acquire_owned().await.unwrap();
 
-                // Load assets concurrently (cache warming)
-                let protocol = Url::parse(&url)
-                    .map(|p| p.scheme().to_string())
-                    .unwrap_or_else(|_| "https".to_string());
+                    // Return cached links without re-fetching the page
+                    if let Some(cached) = link_cache.lock().unwrap().get(&url).cloned() {
+                        return (cached, None);
+                    }
 
-                let mut asset_handles = vec![];
-                for mut asset_url in extract_assets(&html_content, &base_url) {
-                    if normalize_url(&asset_url) == normalize_url(&url) {
-                        continue;
+                    // Fetch page
+                    let result = make_request(&url, false, true, ua.clone(), true).await;
+                    record_result(&stats, &result);
+                    let outcome = Outcome::of(&result);
+
+                    let Some(html_content) = result.body else {
+                        link_cache.lock().unwrap().insert(url.clone(), vec![]);
+                        return (vec![], Some(outcome));
+                    };
+
+                    // Load assets concurrently (cache warming), resolved against this page
+                    let protocol = Url::parse(&url)
+                        .map(|p| p.scheme().to_string())
+                        .unwrap_or_else(|_| "https".to_string());
+
+                    let (assets, stylesheets) = extract_assets(&html_content, &url);
+                    let allowed = |a: &String| robots.is_allowed(a) && !url_filter().is_excluded(a);
+                    let mut assets: Vec<String> =
+                        assets.into_iter().filter(|a| allowed(a)).collect();
+                    let stylesheets: Vec<String> =
+                        stylesheets.into_iter().filter(|a| allowed(a)).collect();
+                    let (css_assets, already_fetched) = expand_stylesheets(
+                        &stylesheets,
+                        &url,
+                        &protocol,
+                        stats.clone(),
+                        false,
+                        ua.clone(),
+                    )
+                    .await;
+                    assets.extend(css_assets.into_iter().filter(|a| robots.is_allowed(a)));
+                    assets.sort();
+                    assets.dedup();
+
+                    let mut asset_handles = vec![];
+                    for asset_url in assets {
+                        if normalize_url(&asset_url) == normalize_url(&url) {
+                            continue;
+                        }
+                        let asset_url = match_page_protocol(&asset_url, &protocol);
+                        if already_fetched.contains(&asset_url) {
+                            continue;
+                        }
+                        let stats = stats.clone();
+                        let ua = ua.clone();
+                        asset_handles.push(tokio::spawn(async move {
+                            let result = make_request(&asset_url, false, false, ua, false).await;
+                            record_result(&stats, &result);
+                        }));
                     }
-                    if asset_url.starts_with("http://") && protocol == "https" {
-                        asset_url = asset_url.replace("http://", "https://");
-                    } else if asset_url.starts_with("https://") && protocol == "http" {
-                        asset_url = asset_url.replace("https://", "http://");
+                    for h in asset_handles {
+                        let _ = h.await;
                     }
-                    let stats = stats.clone();
-                    let ua = ua.clone();
-                    asset_handles.push(tokio::spawn(async move {
-                        let (s, t, sz, _, _) =
-                            make_request(&asset_url, false, false, ua, false).await;
-                        stats.lock().unwrap().add_transaction(t, sz, s);
-                    }));
-                }
-                for h in asset_handles {
-                    let _ = h.await;
-                }
 
-                // Extract and cache same-domain links
-                let links = extract_links(&html_content, &base_url);
-                link_cache
-                    .lock()
-                    .unwrap()
-                    .insert(url.clone(), links.clone());
-                links
-            }));
+                    // Extract and cache in-scope links, plus enabled rel= relationships
+                    let mut links = extract_links(&html_content, &url, &limits);
+                    let hints = discovery.page_hints(&html_content, &url);
+                    links.extend(hints.related.into_iter().filter(|l| limits.in_scope(l)));
+                    if let Some(canonical) = hints.canonical
+                        && limits.in_scope(&canonical)
+                    {
+                        non_canonical.lock().unwrap().insert(url.clone());
+                        links.push(canonical);
+                    }
+                    link_cache
+                        .lock()
+                        .unwrap()
+                        .insert(url.clone(), links.clone());
+                    (links, Some(outcome))
+                }),
+            ));
         }
 
-        // Collect all links returned by this wave
-        let mut new_links: Vec<String> = Vec::new();
-        for h in handles {
-            if let Ok(links) = h.await {
-                new_links.extend(links);
+        // Collect all links returned by this wave, with the depth they'd be queued at
+        let mut new_links: Vec<(String, usize)> = Vec::new();
+        let mut outcomes: Vec<(String, Outcome)> = Vec::new();
+        for (url, depth, h) in handles {
+            if let Ok((links, outcome)) = h.await {
+                if let Some(outcome) = outcome {
+                    outcomes.push((url, outcome));
+                }
+                if !limits.depth_allows(depth) {
+                    depth_limited += links.len();
+                    continue;
+                }
+                new_links.extend(links.into_iter().map(|link| (link, depth + 1)));
             }
         }
 
-        // Enqueue only URLs we haven't seen, up to the cap
+        // Enqueue only URLs we haven't seen and robots.txt allows, up to the page cap
         let mut vis = visited.lock().unwrap();
-        for link in new_links {
-            if vis.len() >= max_urls {
+        for (link, depth) in new_links {
+            let key = normalize_url(&link);
+            if vis.contains_key(&key) {
+                continue;
+            }
+            if limits.pages_reached(vis.len()) {
+                page_limit_hit = true;
                 break;
             }
-            if vis.insert(link.clone()) {
-                frontier.push(link);
+            if !robots.is_allowed(&link) {
+                disallowed.insert(link);
+                continue;
+            }
+            if url_filter().is_excluded(&link) {
+                continue;
+            }
+            if vis.insert(key, link.clone()).is_none() {
+                if let Some(state) = crawl_state() {
+                    state.queue(Phase::Discovery, &link, depth);
+                }
+                frontier.push((link, depth));
             }
         }
+
+        // Checkpoint the wave only after its links are queued
+        if let Some(state) = crawl_state() {
+            for (url, outcome) in outcomes {
+                state.record(Phase::Discovery, &url, outcome);
+            }
+            state.checkpoint();
+        }
     }
 
-    let mut result: Vec<String> = visited.lock().unwrap().iter().cloned().collect();
+    // Pages outside the include patterns, and duplicates of a canonical URL, were crawled
+    // for their links but aren't warmed
+    let non_canonical = non_canonical.lock().unwrap();
+    let mut result: Vec<String> = visited
+        .lock()
+        .unwrap()
+        .values()
+        .filter(|u| !non_canonical.contains(*u) && url_filter().allows(u))
+        .cloned()
+        .collect();
     result.sort();
     println!("Discovered {} unique URLs by following links", result.len());
+    if !non_canonical.is_empty() {
+        println!(
+            "Skipped {} URLs whose rel=canonical points elsewhere",
+            non_canonical.len()
+        );
+    }
+    if !disallowed.is_empty() {
+        println!("Skipped {} URLs disallowed by robots.txt", disallowed.len());
+    }
+    limits.report(page_limit_hit, depth_limited);
     Ok(result)
 }
 
@@ -847,88 +2094,308 @@ async fn crawl_js_site(
     concurrency: usize,
     stats: Arc<Mutex<Stats>>,
     discovery_threads: Option<usize>,
+    robots: Arc<Robots>,
+    limits: Arc<CrawlLimits>,
 ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-    js_crawler::crawl_js_site(start_url, concurrency, stats, discovery_threads).await
+    js_crawler::crawl_js_site(
+        start_url,
+        concurrency,
+        stats,
+        discovery_threads,
+        robots,
+        limits,
+    )
+    .await
 }
 
-/// Extract static assets from HTML content
-fn extract_assets(html_content: &str, base_url: &str) -> Vec<String> {
+/// Extract static assets from HTML content, resolved against the page URL or its
+/// `<base href>`: stylesheets, icons, preloads and manifests
+/// from `<link>`, scripts, images (including `srcset` and `<picture>` sources), video/audio
+/// sources and posters, `og:image`/`twitter:image` meta tags and `url()` references in
+/// inline styles. Only assets allowed by the asset type and third-party policy are returned,
+/// along with the stylesheets among them: `rel="stylesheet"` and `as="style"` links and
+/// `@import`s in `<style>` blocks, whatever their URLs look like.
+fn extract_assets(html_content: &str, page_url: &str) -> (Vec<String>, Vec<String>) {
     let mut assets = Vec::new();
-    let html = Html::parse_fragment(html_content);
-
-    // Extract CSS links
-    if let Ok(links_selector) = Selector::parse("link[href]") {
-        for link in html.select(&links_selector) {
-            if let Some(href) = link.value().attr("href") {
-                if let Ok(asset_url) = build_asset_url(href, base_url) {
-                    assets.push(asset_url);
-                }
+    let mut stylesheets = Vec::new();
+    let html = Html::parse_document(html_content);
+    let Some(base) = url_norm::document_base(&html, page_url) else {
+        return (assets, stylesheets);
+    };
+
+    let mut push = |value: &str, kind: AssetType| {
+        if let Some(asset_url) = url_norm::resolve(value, &base)
+            && asset_policy().allows(&asset_url, kind, page_url)
+        {
+            if kind == AssetType::Stylesheet {
+                stylesheets.push(asset_url.clone());
+            }
+            assets.push(asset_url);
+        }
+    };
+
+    // <link> targets are typed by rel; canonical, alternate, feed and pagination links
+    // point at pages rather than assets and are skipped
+    if let Ok(link_selector) = Selector::parse("link[href][rel]") {
+        for element in html.select(&link_selector) {
+            let link = element.value();
+            let (Some(href), Some(rel)) = (link.attr("href"), link.attr("rel")) else {
+                continue;
+            };
+            if let Some(kind) = AssetType::from_link(rel, link.attr("as"), href) {
+                push(href, kind);
             }
         }
     }
 
-    // Extract JavaScript files
-    if let Ok(script_selector) = Selector::parse("script[src]") {
-        for script in html.select(&script_selector) {
-            if let Some(src) = script.value().attr("src") {
-                if let Ok(asset_url) = build_asset_url(src, base_url) {
-                    assets.push(asset_url);
+    // (selector, attribute, type) for elements whose attribute is a single asset URL:
+    // scripts, images, media sources, posters and share images
+    let single_url_attrs = [
+        ("script[src]", "src", AssetType::Script),
+        ("img[src]", "src", AssetType::Image),
+        ("picture source[src]", "src", AssetType::Image),
+        ("video[src]", "src", AssetType::Media),
+        ("video source[src]", "src", AssetType::Media),
+        ("video[poster]", "poster", AssetType::Image),
+        ("audio[src]", "src", AssetType::Media),
+        ("audio source[src]", "src", AssetType::Media),
+        ("track[src]", "src", AssetType::Media),
+        (
+            "meta[property=\"og:image\"][content]",
+            "content",
+            AssetType::Image,
+        ),
+        (
+            "meta[name=\"twitter:image\"][content]",
+            "content",
+            AssetType::Image,
+        ),
+    ];
+    for (selector, attr, kind) in single_url_attrs {
+        if let Ok(selector) = Selector::parse(selector) {
+            for element in html.select(&selector) {
+                if let Some(value) = element.value().attr(attr) {
+                    push(value, kind);
                 }
             }
         }
     }
 
-    // Extract images
-    if let Ok(img_selector) = Selector::parse("img[src]") {
-        for img in html.select(&img_selector) {
-            if let Some(src) = img.value().attr("src") {
-                if !src.starts_with("data:image/") {
-                    if let Ok(asset_url) = build_asset_url(src, base_url) {
-                        assets.push(asset_url);
-                    }
+    // Responsive images: <img srcset> and <picture><source srcset>
+    if let Ok(srcset_selector) = Selector::parse("img[srcset], source[srcset]") {
+        for element in html.select(&srcset_selector) {
+            if let Some(srcset) = element.value().attr("srcset") {
+                for candidate in parse_srcset(srcset) {
+                    push(candidate, AssetType::Image);
                 }
             }
         }
     }
 
-    assets
+    // url() and @import references in style attributes and <style> blocks
+    let mut inline_css = CssReferences::default();
+    if let Ok(style_selector) = Selector::parse("[style]") {
+        for element in html.select(&style_selector) {
+            if let Some(style) = element.value().attr("style") {
+                inline_css.extend(extract_css_urls(style, base.as_str()));
+            }
+        }
+    }
+    if let Ok(style_selector) = Selector::parse("style") {
+        for element in html.select(&style_selector) {
+            let css: String = element.text().collect();
+            inline_css.extend(extract_css_urls(&css, base.as_str()));
+        }
+    }
+    for url in inline_css.urls {
+        if asset_policy().allows(&url, css_reference_type(&url), page_url) {
+            assets.push(url);
+        }
+    }
+    for url in inline_css.imports {
+        if asset_policy().allows(&url, AssetType::Stylesheet, page_url) {
+            stylesheets.push(url.clone());
+            assets.push(url);
+        }
+    }
+
+    (assets, stylesheets)
 }
 
-/// Extract links from HTML content
-fn extract_links(html_content: &str, base_url: &str) -> Vec<String> {
-    let mut links = Vec::new();
-    let html = Html::parse_fragment(html_content);
+/// Split a `srcset` attribute into its candidate URLs, dropping width/density descriptors.
+/// As in the HTML spec, a URL runs to the next whitespace and may itself contain commas
+/// (`/img?w=100,h=50 1x`); commas at its end separate candidates.
+fn parse_srcset(srcset: &str) -> Vec<&str> {
+    let mut candidates = Vec::new();
+    let mut rest = srcset;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if rest.is_empty() {
+            break;
+        }
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let (url, after) = rest.split_at(end);
+        let trimmed = url.trim_end_matches(',');
+        candidates.push(trimmed);
+        rest = if trimmed.len() < url.len() {
+            after
+        } else {
+            // Skip the descriptors up to the comma that ends the candidate
+            after.find(',').map_or("", |comma| &after[comma + 1..])
+        };
+    }
+    candidates
+}
+
+/// References found in CSS. `@import` targets are stylesheets whatever their URLs look
+/// like; everything else is a font, image or other `url()` asset.
+#[derive(Clone, Debug, Default)]
+struct CssReferences {
+    urls: Vec<String>,
+    imports: Vec<String>,
+}
+
+impl CssReferences {
+    fn extend(&mut self, other: CssReferences) {
+        self.urls.extend(other.urls);
+        self.imports.extend(other.imports);
+    }
+}
+
+/// Extract `url()` and `@import` references from CSS, resolved against `css_url`
+/// (the stylesheet's own URL, or the page for inline styles).
+fn extract_css_urls(css: &str, css_url: &str) -> CssReferences {
+    static CSS_URL_RE: OnceLock<Regex> = OnceLock::new();
+    static CSS_IMPORT_RE: OnceLock<Regex> = OnceLock::new();
+    let url_re =
+        CSS_URL_RE.get_or_init(|| Regex::new(r#"(?i)url\(\s*['"]?([^'")]+?)['"]?\s*\)"#).unwrap());
+    let import_re = CSS_IMPORT_RE
+        .get_or_init(|| Regex::new(r#"(?i)@import\s+(?:url\(\s*)?['"]?([^'")\s;]+)"#).unwrap());
+
+    let Ok(base) = Url::parse(css_url) else {
+        return CssReferences::default();
+    };
+    let resolve = |captures: regex::CaptureMatches| -> Vec<String> {
+        captures
+            .filter_map(|caps| caps.get(1))
+            .map(|m| m.as_str().trim())
+            .filter(|r| !r.is_empty() && !r.starts_with("data:") && !r.starts_with('#'))
+            .filter_map(|r| base.join(r).ok())
+            .filter(|u| u.scheme() == "http" || u.scheme() == "https")
+            .map(|u| u.to_string())
+            .collect()
+    };
+    // `@import url(...)` matches both patterns; it is an import
+    let imports = resolve(import_re.captures_iter(css));
+    let urls = resolve(url_re.captures_iter(css))
+        .into_iter()
+        .filter(|u| !imports.contains(u))
+        .collect();
+    CssReferences { urls, imports }
+}
+
+/// Type of a `url()`/`@import` reference found in CSS. Extensionless references are
+/// almost always background images.
+fn css_reference_type(url: &str) -> AssetType {
+    AssetType::from_url(url).unwrap_or(AssetType::Image)
+}
 
-    // Get the base domain - if we can't extract it, we'll accept all links
-    let base_domain = extract_domain(base_url);
+/// Rewrite an asset URL to the page's scheme, so http assets on an https page are
+/// warmed over https and vice versa.
+fn match_page_protocol(asset_url: &str, protocol: &str) -> String {
+    if asset_url.starts_with("http://") && protocol == "https" {
+        asset_url.replacen("http://", "https://", 1)
+    } else if asset_url.starts_with("https://") && protocol == "http" {
+        asset_url.replacen("https://", "http://", 1)
+    } else {
+        asset_url.to_string()
+    }
+}
+
+/// Fetch `stylesheets` and collect the fonts, background images and imported
+/// stylesheets they reference, following `@import`s recursively. References are
+/// checked against the asset policy for the page at `page_url`.
+/// Each stylesheet's references are cached in `stylesheets()`, so it is fetched and
+/// parsed once per run. Returns the discovered URLs and the stylesheets fetched along
+/// the way, which already count as warmed for this page.
+async fn expand_stylesheets(
+    stylesheet_urls: &[String],
+    page_url: &str,
+    protocol: &str,
+    stats: Arc<Mutex<Stats>>,
+    verbose: bool,
+    user_agent_mode: Arc<UserAgentMode>,
+) -> (Vec<String>, HashSet<String>) {
+    let mut discovered = Vec::new();
+    let mut fetched = HashSet::new();
+    let mut seen = HashSet::new();
+    let mut queue: Vec<String> = stylesheet_urls
+        .iter()
+        .map(|a| match_page_protocol(a, protocol))
+        .collect();
+
+    while let Some(css_url) = queue.pop() {
+        if !seen.insert(css_url.clone()) {
+            continue;
+        }
 
-    // Extract anchor links
+        let cached = stylesheets().lock().unwrap().get(&css_url).cloned();
+        let references = match cached {
+            Some(references) => references,
+            None => {
+                let result =
+                    make_request(&css_url, verbose, false, user_agent_mode.clone(), true).await;
+                record_result(&stats, &result);
+                fetched.insert(css_url.clone());
+
+                let mut references = result
+                    .body
+                    .map(|css| extract_css_urls(&css, &css_url))
+                    .unwrap_or_default();
+                references.urls.retain(|r| !url_filter().is_excluded(r));
+                references.imports.retain(|r| !url_filter().is_excluded(r));
+                stylesheets()
+                    .lock()
+                    .unwrap()
+                    .insert(css_url.clone(), references.clone());
+                references
+            }
+        };
+
+        for reference in references.urls {
+            if asset_policy().allows(&reference, css_reference_type(&reference), page_url) {
+                discovered.push(match_page_protocol(&reference, protocol));
+            }
+        }
+        for reference in references.imports {
+            if asset_policy().allows(&reference, AssetType::Stylesheet, page_url) {
+                let reference = match_page_protocol(&reference, protocol);
+                queue.push(reference.clone());
+                discovered.push(reference);
+            }
+        }
+    }
+
+    (discovered, fetched)
+}
+
+/// Extract links from HTML content, resolved against the page URL or its `<base href>`,
+/// keeping only those inside the crawl scope
+fn extract_links(html_content: &str, page_url: &str, limits: &CrawlLimits) -> Vec<String> {
+    let mut links = Vec::new();
+    let html = Html::parse_document(html_content);
+    let Some(base) = url_norm::document_base(&html, page_url) else {
+        return links;
+    };
+
+    // Extract anchor links; anchors, javascript:, mailto: and tel: links don't resolve
     if let Ok(a_selector) = Selector::parse("a[href]") {
         for a in html.select(&a_selector) {
-            if let Some(href) = a.value().attr("href") {
-                // Skip empty links, anchors, javascript, mailto, tel links
-                if href.is_empty()
-                    || href.starts_with('#')
-                    || href.starts_with("javascript:")
-                    || href.starts_with("mailto:")
-                    || href.starts_with("tel:")
-                {
-                    continue;
-                }
-
-                if let Ok(link_url) = build_asset_url(href, base_url) {
-                    // Only include links from the same domain if we have a base domain
-                    match (&base_domain, extract_domain(&link_url)) {
-                        (Some(base), Some(link)) if base == &link => {
-                            links.push(link_url);
-                        }
-                        (None, _) => {
-                            // If we couldn't extract base domain, include all links
-                            links.push(link_url);
-                        }
-                        _ => {} // Different domains or couldn't extract link domain
-                    }
-                }
+            if let Some(href) = a.value().attr("href")
+                && let Some(link_url) = url_norm::resolve(href, &base)
+                && limits.in_scope(&link_url)
+            {
+                links.push(link_url);
             }
         }
     }
@@ -938,10 +2405,10 @@ fn extract_links(html_content: &str, base_url: &str) -> Vec<String> {
 
 /// Extract domain from URL
 fn extract_domain(url: &str) -> Option<String> {
-    if let Ok(parsed_url) = Url::parse(url) {
-        if let Some(host) = parsed_url.host_str() {
-            return Some(host.to_string());
-        }
+    if let Ok(parsed_url) = Url::parse(url)
+        && let Some(host) = parsed_url.host_str()
+    {
+        return Some(host.to_string());
     }
     None
 }
@@ -961,28 +2428,17 @@ fn default_product_user_agent() -> String {
     "warmer/0.1.2 (+https://abh.ai/warmer)".to_string()
 }
 
-fn load_config(path: &str) -> FileConfig {
-    let contents = match fs::read_to_string(path) {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!(
-                "Failed to read config file {}: {}. Using CLI options only.",
-                path, e
-            );
-            return FileConfig::default();
-        }
-    };
-
-    match toml::from_str::<FileConfig>(&contents) {
-        Ok(cfg) => cfg,
-        Err(e) => {
-            eprintln!(
-                "Failed to parse config file {} as TOML: {}. Using CLI options only.",
-                path, e
-            );
-            FileConfig::default()
-        }
-    }
+/// Read the config file. A file that cannot be used is an error rather than silently
+/// ignored, since it may hold the thresholds a CI run is gated on.
+fn load_config(path: &str) -> Result<FileConfig, ConfigError> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| ConfigError(format!("Failed to read config file {}: {}", path, e)))?;
+    toml::from_str::<FileConfig>(&contents).map_err(|e| {
+        ConfigError(format!(
+            "Failed to parse config file {} as TOML: {}",
+            path, e
+        ))
+    })
 }
 
 fn build_user_agent_mode(resolved: &ResolvedConfig) -> UserAgentMode {
@@ -1039,40 +2495,9 @@ fn get_user_agent(mode: &UserAgentMode) -> String {
     }
 }
 
-/// Normalize URL for comparison (ignore http/https difference and trailing slashes)
+/// Normalize URL for deduplication (see `url_norm::normalize`)
 fn normalize_url(url: &str) -> String {
-    // Remove protocol (http:// or https://)
-    let without_protocol = url.replace("http://", "").replace("https://", "");
-
-    // Remove trailing slash if present
-    let mut normalized = without_protocol.trim_end_matches('/').to_string();
-
-    // Add domain if it's just a path
-    if !normalized.contains('.') && !normalized.is_empty() {
-        normalized = format!(
-            "abh.ai{}",
-            if normalized.starts_with('/') {
-                normalized
-            } else {
-                format!("/{}", normalized)
-            }
-        );
-    }
-
-    normalized
-}
-
-/// Build full URL for asset
-fn build_asset_url(asset_path: &str, base_url: &str) -> Result<String, url::ParseError> {
-    if asset_path.starts_with("http://") || asset_path.starts_with("https://") {
-        Ok(asset_path.to_string())
-    } else if asset_path.starts_with("//") {
-        Ok(format!("https:{}", asset_path))
-    } else if asset_path.starts_with("/") {
-        Ok(format!("{}{}", base_url, asset_path))
-    } else {
-        Ok(format!("{}/{}", base_url, asset_path))
-    }
+    url_norm::normalize(url, SORT_QUERY.load(Ordering::Relaxed))
 }
 
 /// Make a single HTTP request asynchronously.
@@ -1082,44 +2507,99 @@ fn build_asset_url(asset_path: &str, base_url: &str) -> Result<String, url::Pars
 /// in a background task so the connection can be reused (keep-alive). This is what we
 /// want for load testing and for asset fetches — we only care that the server served a
 /// response, not about its contents.
+///
+/// Redirects are followed here rather than by libcurl so that every hop can be
+/// recorded and the `--redirects` policy (none, same-host, limit) can be applied.
 async fn make_request(
     url: &str,
-    _verbose: bool,
+    verbose: bool,
     is_main_url: bool,
     user_agent_mode: Arc<UserAgentMode>,
     need_body: bool,
-) -> (u16, f64, u64, Option<String>, String) {
+) -> RequestResult {
+    // Wait for the host's crawl politeness limits and the rate limits before the
+    // timer starts
+    let _host_permit = match HOST_LIMITS.get() {
+        Some(limits) => Some(limits.acquire(url).await),
+        None => None,
+    };
+    let wait = rate_limits().reserve(url, is_main_url);
+    if !wait.is_zero() {
+        sleep(wait).await;
+    }
     let start = Instant::now();
+    let started_at = Utc::now();
     let user_agent = get_user_agent(&user_agent_mode);
+    let redirect = redirect_settings();
+
+    let mut current_url = url.to_string();
+    let mut redirects: Vec<RedirectHop> = Vec::new();
+    let mut redirect_stopped = None;
+
+    let mut resp = loop {
+        let req = match build_page_request(&current_url, &user_agent) {
+            Ok(r) => r,
+            Err(e) => {
+                let error = RequestError::invalid_request(&e);
+                let mut result = request_error(start, url, verbose, is_main_url, error);
+                result.redirects = redirects;
+                return result;
+            }
+        };
 
-    let mut builder = Request::get(url)
-        .header("User-Agent", user_agent)
-        .header(
-            "Accept",
-            "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8",
-        )
-        .header("Accept-Language", "en-US,en;q=0.9")
-        .header("Accept-Encoding", "gzip, deflate")
-        .header("Connection", "keep-alive")
-        .ssl_options(
-            SslOption::DANGER_ACCEPT_INVALID_CERTS
-                | SslOption::DANGER_ACCEPT_REVOKED_CERTS
-                | SslOption::DANGER_ACCEPT_INVALID_HOSTS,
-        )
-        .redirect_policy(RedirectPolicy::Follow);
-    if FORCE_HTTP1.load(Ordering::Relaxed) {
-        builder = builder.version_negotiation(VersionNegotiation::http11());
-    }
-    let req_result = builder.body(());
+        let resp = match http_client().send_async(req).await {
+            Ok(r) => r,
+            Err(e) => {
+                let error = RequestError::from_isahc(&e);
+                let mut result = request_error(start, url, verbose, is_main_url, error);
+                result.redirects = redirects;
+                return result;
+            }
+        };
 
-    let req = match req_result {
-        Ok(r) => r,
-        Err(_) => return request_error(start, url, _verbose, is_main_url),
-    };
+        if !resp.status().is_redirection() {
+            break resp;
+        }
+        let Some(location) = resp
+            .headers()
+            .get("location")
+            .and_then(|h| h.to_str().ok())
+            .and_then(|l| Url::parse(&current_url).ok()?.join(l).ok())
+        else {
+            break resp;
+        };
+
+        redirects.push(RedirectHop {
+            status_code: resp.status().as_u16(),
+            location: location.to_string(),
+        });
 
-    let mut resp = match http_client().send_async(req).await {
-        Ok(r) => r,
-        Err(_) => return request_error(start, url, _verbose, is_main_url),
+        if redirect.mode == RedirectMode::None {
+            redirect_stopped = Some("not followed");
+            break resp;
+        }
+        if redirect.mode == RedirectMode::SameHost
+            && extract_domain(&current_url).as_deref() != location.host_str()
+        {
+            redirect_stopped = Some("cross-host, not followed");
+            break resp;
+        }
+        if redirects.len() > redirect.max {
+            let error = RequestError {
+                category: ErrorCategory::TooManyRedirects,
+                message: format!("more than {} redirects", redirect.max),
+            };
+            let mut result = request_error(start, url, verbose, is_main_url, error);
+            result.redirects = redirects;
+            return result;
+        }
+
+        // Drain the redirect body so the connection goes back to the pool.
+        tokio::spawn(async move {
+            let mut resp = resp;
+            let _ = resp.consume().await;
+        });
+        current_url = location.to_string();
     };
 
     // Response headers are in — stop the TTFB clock before we touch the body.
@@ -1136,6 +2616,10 @@ async fn make_request(
     }
     .to_string();
 
+    let cache_hit = cache_hit(resp.headers());
+    let timings = resp.metrics().map(Timings::of);
+    let details = verbose.then(|| response_details(&resp, cache_hit, timings));
+
     // Content-Length for reporting bytes even when we skip the body.
     let content_length: u64 = resp
         .headers()
@@ -1171,18 +2655,102 @@ async fn make_request(
         data_size,
         "GET",
         display_path,
-        _verbose,
+        details.as_deref(),
         is_main_url,
         &http_version,
     );
 
-    (
+    RequestResult {
+        url: url.to_string(),
+        started_at,
         status_code,
         response_time,
         data_size,
-        html_content,
+        body: html_content,
         http_version,
-    )
+        error: None,
+        redirects,
+        redirect_stopped,
+        cache_hit,
+        is_main_url,
+        timings,
+    }
+}
+
+/// Connection timings, cache status and response headers, shown below the request
+/// line at the verbose output level. Timings are cumulative from the request start.
+fn response_details<B>(
+    resp: &isahc::Response<B>,
+    cache_hit: Option<bool>,
+    timings: Option<Timings>,
+) -> String {
+    let mut lines = Vec::new();
+    if let Some(timings) = timings {
+        lines.push(format!(
+            "dns {:.1} ms, connect {:.1} ms, tls {:.1} ms, first byte {:.1} ms",
+            timings.dns, timings.connect, timings.tls, timings.first_byte
+        ));
+    }
+    lines.push(format!(
+        "cache: {}",
+        match cache_hit {
+            Some(true) => "HIT".green(),
+            Some(false) => "MISS".yellow(),
+            None => "unknown".normal(),
+        }
+    ));
+    for (name, value) in resp.headers() {
+        lines.push(format!(
+            "< {}: {}",
+            name,
+            value.to_str().unwrap_or("<binary>")
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Whether a CDN or proxy served the response from its cache, judged from the common
+/// cache status headers (`CF-Cache-Status`, `X-Cache`, `X-Cache-Status`,
+/// `X-Proxy-Cache`) or, failing those, a non-zero `Age`.
+fn cache_hit(headers: &isahc::http::HeaderMap) -> Option<bool> {
+    for name in [
+        "cf-cache-status",
+        "x-cache",
+        "x-cache-status",
+        "x-proxy-cache",
+    ] {
+        if let Some(value) = headers.get(name).and_then(|v| v.to_str().ok()) {
+            return Some(value.to_uppercase().contains("HIT"));
+        }
+    }
+    headers
+        .get("age")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(|age| age > 0)
+}
+
+/// Build a GET request with browser-like headers. Redirects are left to the caller.
+fn build_page_request(url: &str, user_agent: &str) -> Result<Request<()>, isahc::http::Error> {
+    let mut builder = Request::get(url)
+        .header("User-Agent", user_agent)
+        .header(
+            "Accept",
+            "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8",
+        )
+        .header("Accept-Language", "en-US,en;q=0.9")
+        .header("Accept-Encoding", "gzip, deflate")
+        .header("Connection", "keep-alive")
+        .ssl_options(
+            SslOption::DANGER_ACCEPT_INVALID_CERTS
+                | SslOption::DANGER_ACCEPT_REVOKED_CERTS
+                | SslOption::DANGER_ACCEPT_INVALID_HOSTS,
+        )
+        .redirect_policy(RedirectPolicy::None);
+    if FORCE_HTTP1.load(Ordering::Relaxed) {
+        builder = builder.version_negotiation(VersionNegotiation::http11());
+    }
+    builder.body(())
 }
 
 fn request_error(
@@ -1190,89 +2758,113 @@ fn request_error(
     url: &str,
     verbose: bool,
     is_main_url: bool,
-) -> (u16, f64, u64, Option<String>, String) {
-    let response_time = start.elapsed().as_millis() as f64;
-    let default_version = "HTTP/1.1".to_string();
+    error: RequestError,
+) -> RequestResult {
+    let mut result = RequestResult::failed(url, start.elapsed().as_millis() as f64, error);
+    result.is_main_url = is_main_url;
+    let details = result
+        .error
+        .as_ref()
+        .filter(|_| verbose)
+        .map(|error| format!("{}: {}", error.category.to_string().red(), error.message));
     print_transaction(
-        0,
-        response_time,
-        0,
+        result.status_code,
+        result.response_time,
+        result.data_size,
         "GET",
         url,
-        verbose,
+        details.as_deref(),
         is_main_url,
-        &default_version,
+        &result.http_version,
     );
-    (0, response_time, 0, None, default_version)
+    result
 }
 
-/// Crawl mode - process each URL only once
+/// Crawl mode - process each URL only once, with `concurrency` workers sharing one queue
+#[allow(clippy::too_many_arguments)]
 async fn crawl_urls(
     urls: Vec<String>,
+    concurrency: usize,
     stats: Arc<Mutex<Stats>>,
     verbose: bool,
     no_assets: bool,
     user_agent_mode: Arc<UserAgentMode>,
     asset_cache: Arc<Mutex<HashMap<String, Vec<String>>>>,
 ) {
-    let mut processed_urls = std::collections::HashSet::new();
-    let mut urls_to_process = urls;
-
-    while !urls_to_process.is_empty() {
-        let current_url = urls_to_process.remove(0);
-
-        // Skip if already processed
-        if processed_urls.contains(&current_url) {
-            continue;
-        }
-
-        processed_urls.insert(current_url.clone());
+    // URLs an interrupted run already crawled are skipped (--resume)
+    let mut processed_urls = crawl_state()
+        .map(|s| s.resume_point(Phase::Crawl).0)
+        .unwrap_or_default();
+    if !processed_urls.is_empty() {
+        println!("Skipping {} URLs already crawled", processed_urls.len());
+    }
+    let urls_to_process: VecDeque<String> = urls
+        .into_iter()
+        .filter(|url| processed_urls.insert(url.clone()))
+        .collect();
+    dashboard::set_total(urls_to_process.len());
+    let urls_to_process = Arc::new(Mutex::new(urls_to_process));
+
+    let mut workers = vec![];
+    for worker_id in 0..concurrency.max(1) {
+        let urls_to_process = urls_to_process.clone();
+        let stats = stats.clone();
+        let user_agent_mode = user_agent_mode.clone();
+        let asset_cache = asset_cache.clone();
+
+        workers.push(tokio::spawn(request_log::as_user(worker_id, async move {
+            let _active = dashboard::UserGuard::new();
+            while !shutdown::requested() {
+                let Some(current_url) = urls_to_process.lock().unwrap().pop_front() else {
+                    break;
+                };
 
-        // Extract base URL for asset/link loading and preserve the protocol
-        let (base_url, protocol) = if let Ok(parsed_url) = Url::parse(&current_url) {
-            let scheme = parsed_url.scheme();
-            let host = parsed_url.host_str().unwrap_or_default();
-            if host.is_empty() {
-                (current_url.clone(), scheme.to_string())
-            } else {
-                (format!("{}://{}", scheme, host), scheme.to_string())
-            }
-        } else {
-            (current_url.clone(), "https".to_string())
-        };
+                // Preserve the page protocol for its assets
+                let protocol = Url::parse(&current_url)
+                    .map(|p| p.scheme().to_string())
+                    .unwrap_or_else(|_| "https".to_string());
 
-        if no_assets {
-            let (status_code, response_time, data_size, _, _) =
-                make_request(&current_url, verbose, true, user_agent_mode.clone(), false).await;
+                let outcome = if no_assets {
+                    let result =
+                        make_request(&current_url, verbose, true, user_agent_mode.clone(), false)
+                            .await;
+
+                    // Update stats
+                    record_result(&stats, &result);
+                    Outcome::of(&result)
+                } else {
+                    load_assets_from_url(
+                        &current_url,
+                        stats.clone(),
+                        verbose,
+                        true,
+                        &current_url,
+                        &protocol,
+                        user_agent_mode.clone(),
+                        asset_cache.clone(),
+                    )
+                    .await
+                };
 
-            // Update stats
-            {
-                let mut stats = stats.lock().unwrap();
-                stats.add_transaction(response_time, data_size, status_code);
+                dashboard::page_done();
+                if let Some(state) = crawl_state() {
+                    state.record(Phase::Crawl, &current_url, outcome);
+                    state.checkpoint();
+                }
             }
-        } else {
-            load_assets_from_url(
-                &current_url,
-                &base_url,
-                stats.clone(),
-                verbose,
-                true,
-                &current_url,
-                &protocol,
-                user_agent_mode.clone(),
-                asset_cache.clone(),
-            )
-            .await;
-        }
+        })));
+    }
+    for worker in workers {
+        let _ = worker.await;
     }
 }
 
 /// Load static assets from a URL. Fetches the page, then fetches all assets in parallel.
 /// Uses `asset_cache` so the HTML is parsed for assets only on the first visit per URL;
 /// subsequent visits reuse the cached, deduped asset list.
+#[allow(clippy::too_many_arguments)]
 async fn load_assets_from_url(
     url: &str,
-    base_url: &str,
     stats: Arc<Mutex<Stats>>,
     verbose: bool,
     is_main_url: bool,
@@ -1280,13 +2872,13 @@ async fn load_assets_from_url(
     protocol: &str,
     user_agent_mode: Arc<UserAgentMode>,
     asset_cache: Arc<Mutex<HashMap<String, Vec<String>>>>,
-) {
+) -> Outcome {
     let cached = asset_cache.lock().unwrap().get(url).cloned();
 
     // Only read the HTML body on the first visit to this URL (to extract assets).
     // Once assets are cached, we just need headers to know the server responded.
     let need_body = cached.is_none();
-    let (status_code, response_time, data_size, html_content, _) = make_request(
+    let result = make_request(
         url,
         verbose,
         is_main_url,
@@ -1295,16 +2887,31 @@ async fn load_assets_from_url(
     )
     .await;
 
-    {
-        let mut stats = stats.lock().unwrap();
-        stats.add_transaction(response_time, data_size, status_code);
-    }
+    record_result(&stats, &result);
+    let outcome = Outcome::of(&result);
 
     // Use cached asset list if we have one; otherwise parse HTML once and cache it.
+    // Stylesheets are fetched on that first visit to discover their fonts and images;
+    // their references are cached per stylesheet URL for the rest of the run.
+    let mut already_fetched = HashSet::new();
     let assets: Vec<String> = if let Some(list) = cached {
         list
-    } else if let Some(ref html) = html_content {
-        let mut extracted = extract_assets(html, base_url);
+    } else if let Some(ref html) = result.body {
+        let (mut extracted, mut stylesheet_urls) = extract_assets(html, url);
+        extracted.extend(sitemap_media(url));
+        extracted.retain(|a| !url_filter().is_excluded(a));
+        stylesheet_urls.retain(|a| !url_filter().is_excluded(a));
+        let (css_assets, fetched) = expand_stylesheets(
+            &stylesheet_urls,
+            url,
+            protocol,
+            stats.clone(),
+            verbose,
+            user_agent_mode.clone(),
+        )
+        .await;
+        extracted.extend(css_assets);
+        already_fetched = fetched;
         extracted.sort();
         extracted.dedup();
         asset_cache
@@ -1313,35 +2920,35 @@ async fn load_assets_from_url(
             .insert(url.to_string(), extracted.clone());
         extracted
     } else {
-        return;
+        return outcome;
     };
 
     let main_normalized = normalize_url(main_url);
     let mut handles = vec![];
-    for mut asset_url in assets {
+    for asset_url in assets {
         if normalize_url(&asset_url) == main_normalized {
             continue;
         }
-        if asset_url.starts_with("http://") && protocol == "https" {
-            asset_url = asset_url.replace("http://", "https://");
-        } else if asset_url.starts_with("https://") && protocol == "http" {
-            asset_url = asset_url.replace("https://", "http://");
+        let asset_url = match_page_protocol(&asset_url, protocol);
+        if already_fetched.contains(&asset_url) {
+            continue;
         }
 
         let stats = stats.clone();
         let ua = user_agent_mode.clone();
-        handles.push(tokio::spawn(async move {
-            let (s, t, sz, _, _) = make_request(&asset_url, verbose, false, ua, false).await;
-            let mut stats = stats.lock().unwrap();
-            stats.add_transaction(t, sz, s);
+        handles.push(request_log::spawn(async move {
+            let result = make_request(&asset_url, verbose, false, ua, false).await;
+            record_result(&stats, &result);
         }));
     }
     for h in handles {
         let _ = h.await;
     }
+    outcome
 }
 
 /// Run a single user's requests
+#[allow(clippy::too_many_arguments)]
 async fn run_user(
     urls: Arc<Vec<String>>,
     stats: Arc<Mutex<Stats>>,
@@ -1356,6 +2963,7 @@ async fn run_user(
     user_agent_mode: Arc<UserAgentMode>,
     asset_cache: Arc<Mutex<HashMap<String, Vec<String>>>>,
 ) {
+    let _active = dashboard::UserGuard::new();
     let mut rng = std::collections::hash_map::DefaultHasher::new();
     let start_time = Instant::now();
     let mut request_count = 0;
@@ -1368,25 +2976,30 @@ async fn run_user(
         let idx = thread_id % urls.len();
         (idx, idx + 1)
     } else {
-        let urls_per_thread = (urls.len() + total_threads - 1) / total_threads;
+        let urls_per_thread = urls.len().div_ceil(total_threads);
         let s = thread_id * urls_per_thread;
         let e = std::cmp::min(s + urls_per_thread, urls.len());
         (s, e)
     };
 
     loop {
+        // Stop issuing requests once Ctrl-C was pressed
+        if shutdown::requested() {
+            break;
+        }
+
         // Check if we should stop based on duration
-        if let Some(dur) = duration {
-            if start_time.elapsed() >= dur {
-                break;
-            }
+        if let Some(dur) = duration
+            && start_time.elapsed() >= dur
+        {
+            break;
         }
 
         // Check if we should stop based on repetitions
-        if let Some(reps) = repetitions {
-            if request_count >= reps {
-                break;
-            }
+        if let Some(reps) = repetitions
+            && request_count >= reps
+        {
+            break;
         }
 
         // Select URL
@@ -1402,33 +3015,19 @@ async fn run_user(
             urls[idx].clone()
         };
 
-        // Extract base URL for asset loading and preserve the protocol
-        let (base_url, protocol) = if let Ok(parsed_url) = Url::parse(&url) {
-            let scheme = parsed_url.scheme();
-            let host = parsed_url.host_str().unwrap_or_default();
-            if host.is_empty() {
-                (url.clone(), scheme.to_string())
-            } else {
-                (format!("{}://{}", scheme, host), scheme.to_string())
-            }
-        } else {
-            (url.clone(), "https".to_string())
-        };
+        // Preserve the page protocol for its assets
+        let protocol = Url::parse(&url)
+            .map(|p| p.scheme().to_string())
+            .unwrap_or_else(|_| "https".to_string());
 
         // Make request and load assets unless disabled
         if no_assets {
-            let (status_code, response_time, data_size, _, _) =
-                make_request(&url, verbose, true, user_agent_mode.clone(), false).await;
+            let result = make_request(&url, verbose, true, user_agent_mode.clone(), false).await;
 
-            // Update stats
-            {
-                let mut stats = stats.lock().unwrap();
-                stats.add_transaction(response_time, data_size, status_code);
-            }
+            record_result(&stats, &result);
         } else {
             load_assets_from_url(
                 &url,
-                &base_url,
                 stats.clone(),
                 verbose,
                 true,
@@ -1445,7 +3044,9 @@ async fn run_user(
         // Delay between requests with some randomness
         if delay > 0 {
             let random_delay = delay + rand::rng().random_range(0..=delay / 2);
-            sleep(Duration::from_secs(random_delay)).await;
+            if !shutdown::sleep_unless_stopped(Duration::from_secs(random_delay)).await {
+                break;
+            }
         }
     }
 }
@@ -1462,9 +3063,40 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Parse command line arguments first
     let args = Cli::parse();
 
+    if let Some(Command::Compare {
+        baseline,
+        current,
+        regression_tolerance,
+        error_rate_tolerance,
+    }) = args.command
+    {
+        let load = |path: &str| {
+            RunResults::load(path).unwrap_or_else(|e| {
+                eprintln!("Error: {}", e);
+                exit(RunStatus::ConfigError.exit_code());
+            })
+        };
+        let tolerance = Tolerance {
+            percent: regression_tolerance,
+            error_rate: error_rate_tolerance,
+        };
+        let regressions = compare::compare(&load(&baseline), &load(&current), tolerance);
+        if regressions > 0 {
+            eprintln!("{} regressions against the baseline", regressions);
+            exit(RunStatus::Regressed.exit_code());
+        }
+        return Ok(());
+    }
+
     // Load config file (if provided) and merge with CLI into a single resolved config
     let file_cfg = if let Some(ref config_path) = args.config {
-        load_config(config_path)
+        match load_config(config_path) {
+            Ok(cfg) => cfg,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                exit(RunStatus::ConfigError.exit_code());
+            }
+        }
     } else {
         FileConfig::default()
     };
@@ -1482,45 +3114,171 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .unwrap();
 
     // Run our async main function in the runtime
-    runtime.block_on(async_main(resolved, url))
+    let status = match runtime.block_on(async_main(resolved, url)) {
+        Ok(status) => status,
+        Err(e) if e.is::<ConfigError>() => {
+            eprintln!("Error: {}", e);
+            RunStatus::ConfigError
+        }
+        Err(e) => return Err(e),
+    };
+
+    // An interrupted run still prints its summary, but exits like one
+    if shutdown::requested() {
+        exit(130);
+    }
+    if status != RunStatus::Passed {
+        exit(status.exit_code());
+    }
+    Ok(())
 }
 
 async fn async_main(
     resolved: ResolvedConfig,
     url: Option<String>,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<RunStatus, Box<dyn std::error::Error>> {
+    output::set_level(resolved.output);
     FORCE_HTTP1.store(resolved.http1, Ordering::Relaxed);
+    SORT_QUERY.store(resolved.sort_query, Ordering::Relaxed);
+    let thresholds = resolved
+        .thresholds
+        .iter()
+        .map(|expression| Threshold::parse(expression))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(ConfigError)?;
+    if resolved.bucket_interval == 0 {
+        return Err(ConfigError("--bucket-interval must be at least 1 second".to_string()).into());
+    }
+    let baseline = match resolved.compare {
+        Some(ref path) => Some(RunResults::load(path).map_err(ConfigError)?),
+        None => None,
+    };
+    let duration = match resolved.time {
+        Some(ref time_str) => Some(parse_duration(time_str).map_err(ConfigError)?),
+        None => None,
+    };
+    let _ =
+        URL_FILTER.set(UrlFilter::new(&resolved.include, &resolved.exclude).map_err(ConfigError)?);
+    let _ = ASSET_POLICY.set(AssetPolicy::new(
+        &resolved.asset_types,
+        resolved.third_party,
+        &resolved.cdn_hosts,
+    ));
+    let _ = RATE_LIMITS.set(RateLimits::new(
+        resolved.page_rate,
+        resolved.asset_rate,
+        resolved.host_page_rate,
+        resolved.host_asset_rate,
+        resolved.host_rps,
+        resolved.rate_burst,
+    ));
+    let _ = REDIRECTS.set(RedirectSettings {
+        mode: resolved.redirects,
+        max: resolved.max_redirects,
+    });
+
+    if let Some(ref addr) = resolved.metrics_listen {
+        prometheus::serve(addr)
+            .await
+            .map_err(|e| ConfigError(format!("Cannot serve metrics on {}: {}", addr, e)))?;
+    }
+    if let Some(ref path) = resolved.log_requests {
+        let format = resolved
+            .log_format
+            .unwrap_or_else(|| LogFormat::from_path(path));
+        request_log::open(path, format)
+            .await
+            .map_err(|e| ConfigError(format!("Cannot write request log {}: {}", path, e)))?;
+    }
+    let sinks = resolved
+        .push_metrics
+        .iter()
+        .map(|spec| Sink::parse(spec))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(ConfigError)?;
 
     // Setup stats and signal handler
-    let stats = Arc::new(Mutex::new(Stats::new()));
-    let stats_clone = stats.clone();
+    let stats = Arc::new(Mutex::new(Stats::new(Duration::from_secs(
+        resolved.bucket_interval,
+    ))));
 
     // Configure User-Agent strategy from resolved config
     let user_agent_mode = Arc::new(build_user_agent_mode(&resolved));
 
+    // The first Ctrl-C stops new requests and lets the run finish normally; the
+    // second one quits without waiting
     ctrlc::set_handler(move || {
-        let mut stats = stats_clone.lock().unwrap();
-        stats.finish();
-        print_statistics(&stats);
-        exit(0);
+        if shutdown::request() {
+            dashboard::restore_terminal();
+            eprintln!("\nForced quit");
+            exit(130);
+        }
+        eprintln!("\nInterrupted: finishing in-flight requests (press Ctrl-C again to quit now)");
     })?;
 
     // Determine URLs to test - use JS mode, follow-links, or sitemap
     let urls = if let Some(ref url) = url {
-        if resolved.js_mode {
+        // robots.txt rules are only enforced by the link-following crawlers, for every
+        // host they reach
+        let robots = Arc::new(Robots::new(
+            &get_user_agent(&user_agent_mode),
+            (resolved.js_mode || resolved.follow_links) && !resolved.ignore_robots,
+        ));
+
+        let discovery = LinkDiscovery::new(&resolved.discover);
+
+        // Checkpoint progress when a state file is given (or implied by --resume)
+        let state_file = resolved
+            .state_file
+            .clone()
+            .or_else(|| resolved.resume.then(|| "warmer-state.json".to_string()));
+        if let Some(ref path) = state_file {
+            let mode = if resolved.js_mode {
+                "js"
+            } else if resolved.follow_links {
+                "follow-links"
+            } else {
+                "sitemap"
+            };
+            let state = CrawlState::open(path, url, mode, resolved.resume).map_err(ConfigError)?;
+            let _ = CRAWL_STATE.set(state);
+        }
+
+        // Follow-links keeps its historical 500-page cap unless --max-pages is given
+        let crawl_limits = |default_max_pages: Option<usize>| {
+            Arc::new(CrawlLimits::new(
+                url,
+                resolved.max_pages.or(default_max_pages),
+                resolved.max_depth,
+                resolved.scope,
+                &resolved.allow_hosts,
+                resolved.path_prefix.clone(),
+            ))
+        };
+
+        if let Some(urls) = crawl_state().and_then(|s| s.discovered()) {
+            // A resumed run reuses the URL list its interrupted run discovered
+            println!(
+                "Using {} URLs discovered by the interrupted run",
+                urls.len()
+            );
+            urls
+        } else if resolved.js_mode {
             // If JS mode is enabled, use headless Chrome to crawl JavaScript/WASM sites
             match crawl_js_site(
                 url,
                 resolved.concurrent,
                 stats.clone(),
                 resolved.discovery_threads,
+                robots.clone(),
+                crawl_limits(None),
             )
             .await
             {
                 Ok(discovered_urls) => discovered_urls,
                 Err(js_err) => {
                     eprintln!("Failed to crawl JavaScript site: {}", js_err);
-                    return Ok(());
+                    return Ok(RunStatus::Unreachable);
                 }
             }
         } else if resolved.follow_links {
@@ -1530,37 +3288,54 @@ async fn async_main(
                 resolved.concurrent,
                 stats.clone(),
                 user_agent_mode.clone(),
+                robots.clone(),
+                crawl_limits(Some(500)),
+                discovery,
             )
             .await
             {
                 Ok(discovered_urls) => discovered_urls,
                 Err(follow_err) => {
                     eprintln!("Failed to follow links: {}", follow_err);
-                    return Ok(());
+                    return Ok(RunStatus::Unreachable);
                 }
             }
         } else {
             // Try to load sitemap
-            match load_sitemap(url, user_agent_mode.clone()).await {
+            match load_sitemap(
+                url,
+                user_agent_mode.clone(),
+                discovery,
+                &resolved.feeds,
+                resolved.discover_feeds,
+            )
+            .await
+            {
                 Ok(sitemap_urls) => sitemap_urls,
                 Err(e) => {
                     eprintln!(
                         "Failed to load sitemap: {}. Try using --follow-links or --js option.",
                         e
                     );
-                    return Ok(());
+                    return Ok(RunStatus::Unreachable);
                 }
             }
         }
     } else {
         // No URL provided: URL is required, so bail out with a clear error
         eprintln!("Error: URL argument is required. See --help for usage.");
-        return Ok(());
+        return Ok(RunStatus::ConfigError);
     };
 
     if urls.is_empty() {
         eprintln!("No URLs found to test");
-        return Ok(());
+        return Ok(RunStatus::Unreachable);
+    }
+    // An interrupted discovery is incomplete; --resume continues it instead
+    if let Some(state) = crawl_state()
+        && !shutdown::requested()
+    {
+        state.set_discovered(&urls);
     }
 
     let urls = Arc::new(urls);
@@ -1570,17 +3345,15 @@ async fn async_main(
         format!("{} URLs from sitemap", urls.len())
     };
 
-    // Parse duration
-    let duration = if let Some(ref time_str) = resolved.time {
-        Some(parse_duration(time_str)?)
-    } else {
-        None
-    };
-
     // Print header
-    if resolved.crawl {
+    if resolved.output == OutputLevel::Quiet {
+        // Summary only
+    } else if resolved.crawl {
         println!("** WARMER 0.1.2");
-        println!("** Crawling mode - processing each URL only once");
+        println!(
+            "** Crawling mode - processing each URL only once with {} workers",
+            resolved.concurrent
+        );
         println!("** The server is now under load...");
     } else if resolved.js_mode {
         println!("** WARMER 0.1.2");
@@ -1599,70 +3372,299 @@ async fn async_main(
     let asset_cache: Arc<Mutex<HashMap<String, Vec<String>>>> =
         Arc::new(Mutex::new(HashMap::new()));
 
-    // Handle execution modes
-    if resolved.crawl {
-        // Crawl mode - process each URL only once, directly
-        crawl_urls(
-            (*urls).clone(),
+    // Handle execution modes. After Ctrl-C, in-flight requests get the grace period
+    // to finish before the summary is printed.
+    let grace = Duration::from_secs(resolved.grace_period);
+    let dashboard = if !resolved.dashboard {
+        None
+    } else if std::io::stdout().is_terminal() {
+        Some(Dashboard::start(stats.clone(), display_url.clone()))
+    } else {
+        eprintln!("--dashboard needs a terminal; printing requests instead");
+        None
+    };
+    let periodic = (dashboard.is_none() && resolved.output == OutputLevel::Periodic).then(|| {
+        output::start_periodic(
             stats.clone(),
-            resolved.verbose,
-            resolved.no_assets,
-            user_agent_mode.clone(),
-            asset_cache.clone(),
+            Duration::from_secs(resolved.progress_interval.max(1)),
         )
-        .await;
-    } else {
-        // Load testing mode (including JS and follow-links) - spawn concurrent users
-        let mut handles = vec![];
-        let total_threads = resolved.concurrent;
-
-        for thread_id in 0..total_threads {
-            let urls = urls.clone();
-            let stats = stats.clone();
-            let repetitions = resolved.repetitions;
-            let duration = duration;
-            let delay = resolved.delay;
-            let verbose = resolved.verbose;
-            let internet_mode = resolved.internet;
-            let no_assets = resolved.no_assets;
-            let user_agent_mode = user_agent_mode.clone();
-            let asset_cache = asset_cache.clone();
-
-            let handle = tokio::spawn(async move {
-                run_user(
-                    urls,
-                    stats,
-                    repetitions,
-                    duration,
-                    delay,
-                    verbose,
-                    internet_mode,
-                    no_assets,
-                    thread_id,
-                    total_threads,
-                    user_agent_mode,
-                    asset_cache,
-                )
-                .await;
-            });
+    });
+    let push = (!sinks.is_empty()).then(|| {
+        let target = url
+            .as_deref()
+            .or(urls.first().map(String::as_str))
+            .and_then(|u| Url::parse(u).ok())
+            .and_then(|u| u.host_str().map(|h| h.to_lowercase()))
+            .unwrap_or_default();
+        if resolved.output != OutputLevel::Quiet {
+            println!(
+                "Pushing metrics every {}s to {} sink(s), run ID {}",
+                resolved.push_interval.max(1),
+                sinks.len(),
+                resolved.run_id
+            );
+        }
+        MetricsPush::start(
+            stats.clone(),
+            sinks,
+            Tags {
+                run_id: resolved.run_id.clone(),
+                target,
+            },
+            Duration::from_secs(resolved.push_interval.max(1)),
+        )
+    });
+    let run = async {
+        if resolved.crawl {
+            // Crawl mode - process each URL only once, with -c workers
+            let _ = HOST_LIMITS.set(HostLimits::new(resolved.host_concurrency));
+            crawl_urls(
+                (*urls).clone(),
+                resolved.concurrent,
+                stats.clone(),
+                resolved.verbose,
+                resolved.no_assets,
+                user_agent_mode.clone(),
+                asset_cache.clone(),
+            )
+            .await;
+        } else {
+            // Load testing mode (including JS and follow-links) - spawn concurrent users
+            let mut handles = vec![];
+            let total_threads = resolved.concurrent;
+
+            for thread_id in 0..total_threads {
+                let urls = urls.clone();
+                let stats = stats.clone();
+                let repetitions = resolved.repetitions;
+                let delay = resolved.delay;
+                let verbose = resolved.verbose;
+                let internet_mode = resolved.internet;
+                let no_assets = resolved.no_assets;
+                let user_agent_mode = user_agent_mode.clone();
+                let asset_cache = asset_cache.clone();
+
+                let handle = tokio::spawn(request_log::as_user(thread_id, async move {
+                    run_user(
+                        urls,
+                        stats,
+                        repetitions,
+                        duration,
+                        delay,
+                        verbose,
+                        internet_mode,
+                        no_assets,
+                        thread_id,
+                        total_threads,
+                        user_agent_mode,
+                        asset_cache,
+                    )
+                    .await;
+                }));
+
+                handles.push(handle);
+            }
 
-            handles.push(handle);
+            // Wait for all users to complete
+            for handle in handles {
+                handle.await?;
+            }
         }
+        Ok::<(), tokio::task::JoinError>(())
+    };
+    if shutdown::with_grace(run, grace)
+        .await
+        .transpose()?
+        .is_none()
+    {
+        eprintln!(
+            "Grace period of {}s is over, abandoning in-flight requests",
+            resolved.grace_period
+        );
+    }
+    if let Some(dashboard) = dashboard {
+        dashboard.stop();
+    }
+    if let Some(periodic) = periodic {
+        periodic.abort();
+    }
+    if let Some(push) = push {
+        push.stop().await;
+    }
+    request_log::close().await;
 
-        // Wait for all users to complete
-        for handle in handles {
-            handle.await?;
+    // An interrupted run keeps its progress for --resume; a finished one removes the
+    // state file, whichever mode it ran in, so a later --resume starts fresh
+    if let Some(state) = crawl_state() {
+        if shutdown::requested() {
+            state.save();
+        } else {
+            state.complete();
         }
     }
 
     // Finish and print statistics
-    {
-        let mut stats = stats.lock().unwrap();
-        stats.finish();
-        print_statistics(&stats);
+    let mut stats = stats.lock().unwrap();
+    stats.finish();
+    print_statistics(&stats);
+    let verdicts = thresholds::evaluate(&thresholds, &stats);
+
+    // A report that can't be written doesn't stop the others or hide the run's outcome
+    let mut report_failed = false;
+    let mut written = |kind: &str, path: &str, result: Result<(), String>| match result {
+        Ok(()) => println!("{} written to {}", kind, path),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            report_failed = true;
+        }
+    };
+    if let Some(ref path) = resolved.report_html {
+        let config = run_configuration(&resolved, url.as_deref());
+        let html = html_report::render(&stats, &display_url, &config, &verdicts);
+        let result =
+            fs::write(path, html).map_err(|e| format!("Cannot write HTML report {}: {}", path, e));
+        written("HTML report", path, result);
+    }
+    if let Some(ref path) = resolved.report_junit {
+        let result = fs::write(path, junit::render(&stats, &display_url, &verdicts))
+            .map_err(|e| format!("Cannot write JUnit report {}: {}", path, e));
+        written("JUnit report", path, result);
+    }
+    let results = RunResults::of(&stats, &display_url);
+    if let Some(ref path) = resolved.report_json {
+        written("JSON results", path, results.save(path));
     }
+    let regressions = baseline
+        .map(|baseline| compare::compare(&baseline, &results, resolved.tolerance))
+        .unwrap_or(0);
 
-    Ok(())
+    Ok(match run_status(&stats, &verdicts, regressions) {
+        // Only a run that otherwise passed reports the missing report in its exit code
+        RunStatus::Passed if report_failed => RunStatus::ConfigError,
+        status => status,
+    })
+}
+
+/// Pass unless no request got a response, a threshold failed or the run regressed
+/// against the --compare baseline.
+fn run_status(stats: &Stats, verdicts: &[Verdict], regressions: usize) -> RunStatus {
+    let no_response = stats.errors.values().sum::<usize>();
+    if stats.transactions > 0 && no_response == stats.transactions {
+        eprintln!(
+            "Target unreachable: none of the {} requests got a response",
+            stats.transactions
+        );
+        RunStatus::Unreachable
+    } else if verdicts.iter().any(|v| !v.passed) {
+        let failed = verdicts.iter().filter(|v| !v.passed).count();
+        eprintln!("{} of {} thresholds failed", failed, verdicts.len());
+        RunStatus::ThresholdsFailed
+    } else if regressions > 0 {
+        eprintln!("{} regressions against the baseline", regressions);
+        RunStatus::Regressed
+    } else {
+        RunStatus::Passed
+    }
+}
+
+/// Settings that shaped the run, as (name, value) rows for the reports.
+fn run_configuration(resolved: &ResolvedConfig, url: Option<&str>) -> Vec<(&'static str, String)> {
+    fn name<T: clap::ValueEnum>(value: &T) -> String {
+        value
+            .to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default()
+    }
+    let optional = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+
+    let mode = if resolved.js_mode {
+        "JavaScript"
+    } else if resolved.follow_links {
+        "follow links"
+    } else {
+        "sitemap"
+    };
+    let mut rows = vec![
+        ("Target", url.unwrap_or("-").to_string()),
+        (
+            "Mode",
+            format!(
+                "{}{}{}",
+                mode,
+                if resolved.crawl { ", crawl" } else { "" },
+                if resolved.internet { ", internet" } else { "" }
+            ),
+        ),
+        ("Concurrency", resolved.concurrent.to_string()),
+        ("Time", optional(resolved.time.clone())),
+        (
+            "Repetitions",
+            optional(resolved.repetitions.map(|r| r.to_string())),
+        ),
+        ("Delay", format!("{} s", resolved.delay)),
+        (
+            "Assets",
+            if resolved.no_assets {
+                "off".to_string()
+            } else if resolved.asset_types.is_empty() {
+                format!("all, third-party {}", name(&resolved.third_party))
+            } else {
+                format!(
+                    "{}, third-party {}",
+                    resolved
+                        .asset_types
+                        .iter()
+                        .map(name)
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    name(&resolved.third_party)
+                )
+            },
+        ),
+        (
+            "Redirects",
+            format!(
+                "{} (max {})",
+                name(&resolved.redirects),
+                resolved.max_redirects
+            ),
+        ),
+        (
+            "HTTP version",
+            if resolved.http1 {
+                "HTTP/1.1 only"
+            } else {
+                "negotiated"
+            }
+            .to_string(),
+        ),
+        ("Run ID", resolved.run_id.clone()),
+        ("Bucket interval", format!("{} s", resolved.bucket_interval)),
+    ];
+    if !resolved.include.is_empty() {
+        rows.push(("Include", resolved.include.join(" ")));
+    }
+    if !resolved.exclude.is_empty() {
+        rows.push(("Exclude", resolved.exclude.join(" ")));
+    }
+    for (label, rate) in [
+        ("Page rate", resolved.page_rate),
+        ("Asset rate", resolved.asset_rate),
+        ("Per-host page rate", resolved.host_page_rate),
+        ("Per-host asset rate", resolved.host_asset_rate),
+        ("Per-host request rate", resolved.host_rps),
+    ] {
+        if let Some(rate) = rate {
+            rows.push((label, format!("{} /s", rate)));
+        }
+    }
+    if let Some(max) = resolved.host_concurrency {
+        rows.push(("Per-host concurrency", max.to_string()));
+    }
+    if let Some(ref user_agent) = resolved.user_agent {
+        rows.push(("User-Agent", user_agent.clone()));
+    }
+    rows
 }
 
 /// Returns a static string to use in place of missing
@@ -1682,3 +3684,119 @@ fn default_priority() -> String {
 fn default_changefreq() -> String {
     "daily".to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn css_urls_with_and_without_quotes() {
+        let css = r#"
+            .a { background: url(img/a.png); }
+            .b { background: url( "../img/b.jpg" ); }
+            .c { background: URL('/fonts/c.woff2') format("woff2"); }
+            .d { background: url(data:image/png;base64,AAAA); }
+            .e { mask: url(#clip); }
+        "#;
+        let found = extract_css_urls(css, "https://example.com/css/site.css");
+        assert_eq!(
+            found.urls,
+            [
+                "https://example.com/css/img/a.png",
+                "https://example.com/img/b.jpg",
+                "https://example.com/fonts/c.woff2",
+            ]
+        );
+        assert!(found.imports.is_empty());
+    }
+
+    #[test]
+    fn css_imports_are_stylesheets() {
+        let css = r#"
+            @import "base";
+            @import url('theme.css') screen;
+            @IMPORT url(https://cdn.example.net/reset);
+            body { background: url(bg.png) }
+        "#;
+        let found = extract_css_urls(css, "https://example.com/css/site.css");
+        assert_eq!(
+            found.imports,
+            [
+                "https://example.com/css/base",
+                "https://example.com/css/theme.css",
+                "https://cdn.example.net/reset",
+            ]
+        );
+        assert_eq!(found.urls, ["https://example.com/css/bg.png"]);
+    }
+
+    #[test]
+    fn srcset_candidates_drop_descriptors() {
+        assert_eq!(
+            parse_srcset("small.jpg 480w, medium.jpg 800w,large.jpg 1200w"),
+            ["small.jpg", "medium.jpg", "large.jpg"]
+        );
+        assert_eq!(parse_srcset(" a.png , b.png 2x ,"), ["a.png", "b.png"]);
+        assert_eq!(parse_srcset("only.webp"), ["only.webp"]);
+        assert!(parse_srcset("  ").is_empty());
+    }
+
+    #[test]
+    fn srcset_urls_may_contain_commas() {
+        assert_eq!(
+            parse_srcset("/img?w=100,h=50 1x, /img?w=200,h=100 2x"),
+            ["/img?w=100,h=50", "/img?w=200,h=100"]
+        );
+        // A comma inside an unbroken token belongs to the URL; one at its end ends it
+        assert_eq!(parse_srcset("a.jpg,b.jpg 2x"), ["a.jpg,b.jpg"]);
+        assert_eq!(parse_srcset("a.jpg,, b.jpg"), ["a.jpg", "b.jpg"]);
+    }
+
+    #[test]
+    fn assets_resolve_against_the_page_and_base() {
+        let html = r#"<html><head>
+            <link rel="stylesheet" href="/css/site">
+            <link rel="preload" as="style" href="print.css">
+            <link rel="canonical" href="/blog/post">
+            <script src="js/app.js"></script>
+            <style>@import "inline.css"; .hero { background: url(../hero.png) }</style>
+        </head><body>
+            <img src="img/a.png" srcset="img/a-2x.png 2x, https://cdn.example.net/a-3x.png 3x">
+            <video poster="poster.jpg"><source src="/media/clip.mp4"></video>
+            <div style="background-image: url('bg.webp')"></div>
+        </body></html>"#;
+        let (assets, stylesheets) = extract_assets(html, "https://example.com/blog/post");
+        for expected in [
+            "https://example.com/css/site",
+            "https://example.com/blog/print.css",
+            "https://example.com/blog/js/app.js",
+            "https://example.com/blog/img/a.png",
+            "https://example.com/blog/img/a-2x.png",
+            "https://cdn.example.net/a-3x.png",
+            "https://example.com/blog/poster.jpg",
+            "https://example.com/media/clip.mp4",
+            "https://example.com/hero.png",
+            "https://example.com/blog/bg.webp",
+            "https://example.com/blog/inline.css",
+        ] {
+            assert!(assets.iter().any(|a| a == expected), "missing {}", expected);
+        }
+        assert!(!assets.iter().any(|a| a == "https://example.com/blog/post"));
+        assert_eq!(
+            stylesheets,
+            [
+                "https://example.com/css/site",
+                "https://example.com/blog/print.css",
+                "https://example.com/blog/inline.css",
+            ]
+        );
+    }
+
+    #[test]
+    fn assets_honor_base_href() {
+        let html = r#"<html><head><base href="https://static.example.com/v2/"></head>
+            <body><img src="logo.svg"></body></html>"#;
+        let (assets, _) = extract_assets(html, "https://example.com/");
+        assert_eq!(assets, ["https://static.example.com/v2/logo.svg"]);
+    }
+}
@@ -0,0 +1,588 @@
+use crate::Stats;
+use crate::interval::{IntervalStats, IntervalTracker};
+use chrono::Utc;
+use isahc::config::Configurable;
+use isahc::{AsyncReadResponseExt, HttpClient, Request};
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+use url::Url;
+
+/// Latency percentiles pushed for every interval.
+const PERCENTILES: [(&str, f64); 4] = [("p50", 50.0), ("p90", 90.0), ("p95", 95.0), ("p99", 99.0)];
+
+/// Where interval aggregates are pushed, chosen by the scheme of a `--push-metrics` URL.
+pub enum Sink {
+    /// `statsd://host:port`: one UDP datagram per interval, DogStatsD-style tags
+    Statsd { addr: String },
+    /// `influx+http(s)://host/write?...`: InfluxDB line protocol POSTed to the URL
+    Influx { url: String, token: Option<String> },
+    /// `otlp+http(s)://host:4318`: OTLP/HTTP with the JSON encoding
+    Otlp { url: String },
+}
+
+impl Sink {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let url = Url::parse(spec).map_err(|e| format!("Invalid metrics sink {}: {}", spec, e))?;
+        let host = url
+            .host_str()
+            .ok_or_else(|| format!("Metrics sink {} has no host", spec))?;
+        match url.scheme() {
+            "statsd" => Ok(Self::Statsd {
+                addr: format!("{}:{}", host, url.port().unwrap_or(8125)),
+            }),
+            "influx+http" | "influx+https" => Ok(Self::Influx {
+                url: spec["influx+".len()..].to_string(),
+                token: std::env::var("INFLUX_TOKEN").ok().filter(|t| !t.is_empty()),
+            }),
+            "otlp+http" | "otlp+https" => {
+                let mut url = spec["otlp+".len()..].to_string();
+                if matches!(
+                    Url::parse(&url).map(|u| u.path().to_string()).as_deref(),
+                    Ok("/")
+                ) {
+                    url = format!("{}/v1/metrics", url.trim_end_matches('/'));
+                }
+                Ok(Self::Otlp { url })
+            }
+            other => Err(format!(
+                "Unknown metrics sink scheme '{}' in {} (use statsd://, influx+http:// or otlp+http://)",
+                other, spec
+            )),
+        }
+    }
+
+    fn describe(&self) -> &str {
+        match self {
+            Self::Statsd { addr } => addr,
+            Self::Influx { url, .. } | Self::Otlp { url } => url,
+        }
+    }
+
+    async fn push(&self, tags: &Tags, interval: &IntervalStats) -> Result<(), String> {
+        match self {
+            Self::Statsd { addr } => {
+                let socket = UdpSocket::bind("0.0.0.0:0")
+                    .await
+                    .map_err(|e| e.to_string())?;
+                socket
+                    .send_to(statsd_payload(tags, interval).as_bytes(), addr)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                Ok(())
+            }
+            Self::Influx { url, token } => {
+                let mut builder =
+                    Request::post(url).header("Content-Type", "text/plain; charset=utf-8");
+                if let Some(token) = token {
+                    builder = builder.header("Authorization", format!("Token {}", token));
+                }
+                post(builder.body(influx_payload(tags, interval))).await
+            }
+            Self::Otlp { url } => {
+                let request = Request::post(url)
+                    .header("Content-Type", "application/json")
+                    .body(otlp_payload(tags, interval).to_string());
+                post(request).await
+            }
+        }
+    }
+}
+
+/// Client for the HTTP sinks. Unlike the shared client that warms the target site, it
+/// verifies certificates: pushes may carry an InfluxDB token.
+fn client() -> &'static HttpClient {
+    static CLIENT: OnceLock<HttpClient> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        HttpClient::builder()
+            .connect_timeout(Duration::from_secs(10))
+            .timeout(Duration::from_secs(30))
+            .build()
+            .expect("failed to build metrics HttpClient")
+    })
+}
+
+async fn post(request: Result<Request<String>, isahc::http::Error>) -> Result<(), String> {
+    let request = request.map_err(|e| e.to_string())?;
+    let mut response = client()
+        .send_async(request)
+        .await
+        .map_err(|e| e.to_string())?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        let body = response.text().await.unwrap_or_default();
+        Err(format!("HTTP {} {}", response.status(), body.trim()))
+    }
+}
+
+/// Tags attached to every pushed metric.
+pub struct Tags {
+    pub run_id: String,
+    pub target: String,
+}
+
+/// A run ID for `--run-id` when none is given: start time plus a random suffix.
+pub fn default_run_id() -> String {
+    format!(
+        "{}-{:04x}",
+        Utc::now().format("%Y%m%dT%H%M%S"),
+        rand::random::<u16>()
+    )
+}
+
+/// Pushes an aggregate of every interval to all sinks until stopped.
+pub struct MetricsPush {
+    task: JoinHandle<()>,
+    stop: Arc<Notify>,
+}
+
+impl MetricsPush {
+    pub fn start(
+        stats: Arc<Mutex<Stats>>,
+        sinks: Vec<Sink>,
+        tags: Tags,
+        interval: Duration,
+    ) -> Self {
+        let stop = Arc::new(Notify::new());
+        let stopped = stop.clone();
+        let task = tokio::spawn(async move {
+            let mut tracker = IntervalTracker::new();
+            loop {
+                let last = tokio::select! {
+                    _ = sleep(interval) => false,
+                    _ = stopped.notified() => true,
+                };
+                let aggregate = tracker.next(&stats);
+                for sink in &sinks {
+                    if let Err(e) = sink.push(&tags, &aggregate).await {
+                        eprintln!("Metrics push to {} failed: {}", sink.describe(), e);
+                    }
+                }
+                if last {
+                    break;
+                }
+            }
+        });
+        Self { task, stop }
+    }
+
+    /// Push the final, partial interval and wait for it to be sent.
+    pub async fn stop(self) {
+        self.stop.notify_one();
+        let _ = self.task.await;
+    }
+}
+
+/// Name/value pairs shared by all encodings. Latencies are left out of intervals
+/// without requests rather than reported as zero.
+fn gauges(interval: &IntervalStats) -> Vec<(String, f64)> {
+    let mut gauges = vec![("rate".to_string(), interval.rate())];
+    if let (Some(avg), Some(max)) = (interval.avg(), interval.max()) {
+        gauges.push(("latency_avg_ms".to_string(), avg));
+        for (name, p) in PERCENTILES {
+            if let Some(value) = interval.percentile(p) {
+                gauges.push((format!("latency_{}_ms", name), value));
+            }
+        }
+        gauges.push(("latency_max_ms".to_string(), max));
+    }
+    gauges
+}
+
+fn counters(interval: &IntervalStats) -> [(&'static str, u64); 5] {
+    [
+        ("requests", interval.requests as u64),
+        ("failed", interval.failed as u64),
+        ("bytes", interval.bytes),
+        ("cache_hits", interval.cache_hits as u64),
+        ("cache_misses", interval.cache_misses as u64),
+    ]
+}
+
+fn statsd_payload(tags: &Tags, interval: &IntervalStats) -> String {
+    let clean = |value: &str| value.replace([',', '|', '#', ':', '\n'], "_");
+    let tag_list = format!(
+        "#run_id:{},target:{}",
+        clean(&tags.run_id),
+        clean(&tags.target)
+    );
+    let mut lines = Vec::new();
+    for (name, value) in counters(interval) {
+        lines.push(format!("warmer.{}:{}|c|{}", name, value, tag_list));
+    }
+    for (name, value) in gauges(interval) {
+        lines.push(format!("warmer.{}:{:.3}|g|{}", name, value, tag_list));
+    }
+    for (category, count) in &interval.errors {
+        lines.push(format!(
+            "warmer.errors:{}|c|{},category:{}",
+            count,
+            tag_list,
+            category.tag()
+        ));
+    }
+    lines.join("\n")
+}
+
+fn influx_payload(tags: &Tags, interval: &IntervalStats) -> String {
+    let escape = |value: &str| {
+        value
+            .replace('\\', "\\\\")
+            .replace(',', "\\,")
+            .replace('=', "\\=")
+            .replace(' ', "\\ ")
+    };
+    let tag_set = format!(
+        "run_id={},target={}",
+        escape(&tags.run_id),
+        escape(&tags.target)
+    );
+    let timestamp = Utc::now().timestamp_nanos_opt().unwrap_or_default();
+
+    let mut fields: Vec<String> = counters(interval)
+        .iter()
+        .map(|(name, value)| format!("{}={}i", name, value))
+        .collect();
+    fields.extend(
+        gauges(interval)
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value)),
+    );
+    let mut lines = vec![format!(
+        "warmer,{} {} {}",
+        tag_set,
+        fields.join(","),
+        timestamp
+    )];
+    for (category, count) in &interval.errors {
+        lines.push(format!(
+            "warmer_errors,{},category={} count={}i {}",
+            tag_set,
+            category.tag(),
+            count,
+            timestamp
+        ));
+    }
+    lines.join("\n")
+}
+
+fn otlp_payload(tags: &Tags, interval: &IntervalStats) -> Value {
+    let end = Utc::now().timestamp_nanos_opt().unwrap_or_default();
+    let start = end - (interval.seconds * 1e9) as i64;
+    let attribute = |key: &str, value: &str| json!({"key": key, "value": {"stringValue": value}});
+    // Delta sums: each push carries only what happened during the interval
+    let sum = |name: String, points: Vec<Value>| {
+        json!({
+            "name": name,
+            "sum": {
+                "dataPoints": points,
+                "aggregationTemporality": 1,
+                "isMonotonic": true
+            }
+        })
+    };
+    let point = |value: u64, attributes: Vec<Value>| {
+        json!({
+            "startTimeUnixNano": start.to_string(),
+            "timeUnixNano": end.to_string(),
+            "asInt": value.to_string(),
+            "attributes": attributes
+        })
+    };
+
+    let mut metrics: Vec<Value> = counters(interval)
+        .iter()
+        .map(|(name, value)| sum(format!("warmer.{}", name), vec![point(*value, vec![])]))
+        .collect();
+    if !interval.errors.is_empty() {
+        let points = interval
+            .errors
+            .iter()
+            .map(|(category, count)| {
+                point(*count as u64, vec![attribute("category", category.tag())])
+            })
+            .collect();
+        metrics.push(sum("warmer.errors".to_string(), points));
+    }
+    for (name, value) in gauges(interval) {
+        metrics.push(json!({
+            "name": format!("warmer.{}", name),
+            "gauge": {
+                "dataPoints": [{"timeUnixNano": end.to_string(), "asDouble": value}]
+            }
+        }));
+    }
+
+    json!({
+        "resourceMetrics": [{
+            "resource": {
+                "attributes": [
+                    attribute("service.name", "warmer"),
+                    attribute("warmer.run_id", &tags.run_id),
+                    attribute("warmer.target", &tags.target)
+                ]
+            },
+            "scopeMetrics": [{
+                "scope": {"name": "warmer", "version": env!("CARGO_PKG_VERSION")},
+                "metrics": metrics
+            }]
+        }]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ErrorCategory;
+    use crate::histogram::Histogram;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn tags() -> Tags {
+        Tags {
+            run_id: "nightly run,1=a\\b".to_string(),
+            target: "https://example.com/a|b#c:d".to_string(),
+        }
+    }
+
+    fn interval() -> IntervalStats {
+        let mut latency = Histogram::default();
+        for ms in [10.0, 20.0, 30.0, 40.0] {
+            latency.record(ms);
+        }
+        IntervalStats {
+            elapsed: 12.0,
+            seconds: 2.0,
+            requests: 4,
+            failed: 1,
+            bytes: 2048,
+            cache_hits: 3,
+            cache_misses: 1,
+            errors: vec![(ErrorCategory::Timeout, 1)],
+            latency,
+        }
+    }
+
+    fn idle() -> IntervalStats {
+        IntervalStats {
+            requests: 0,
+            failed: 0,
+            bytes: 0,
+            cache_hits: 0,
+            cache_misses: 0,
+            errors: Vec::new(),
+            latency: Histogram::default(),
+            ..interval()
+        }
+    }
+
+    #[test]
+    fn statsd_payload_has_counters_gauges_and_errors_with_clean_tags() {
+        let payload = statsd_payload(&tags(), &interval());
+        let tag_list = "#run_id:nightly run_1=a\\b,target:https_//example.com/a_b_c_d";
+        let lines: Vec<&str> = payload.lines().collect();
+        assert!(lines.contains(&format!("warmer.requests:4|c|{}", tag_list).as_str()));
+        assert!(lines.contains(&format!("warmer.bytes:2048|c|{}", tag_list).as_str()));
+        assert!(lines.contains(&format!("warmer.rate:2.000|g|{}", tag_list).as_str()));
+        assert!(lines.contains(&format!("warmer.latency_avg_ms:25.000|g|{}", tag_list).as_str()));
+        assert!(lines.contains(&format!("warmer.latency_max_ms:40.000|g|{}", tag_list).as_str()));
+        assert!(
+            lines.contains(&format!("warmer.errors:1|c|{},category:timeout", tag_list).as_str())
+        );
+    }
+
+    #[test]
+    fn idle_intervals_push_no_latency() {
+        let payload = statsd_payload(&tags(), &idle());
+        assert!(payload.contains("warmer.requests:0|c|"));
+        assert!(!payload.contains("latency"));
+        assert!(!influx_payload(&tags(), &idle()).contains("latency"));
+    }
+
+    #[test]
+    fn influx_payload_escapes_tag_values() {
+        let payload = influx_payload(&tags(), &interval());
+        let mut lines = payload.lines();
+        let main = lines.next().unwrap();
+        assert!(
+            main.starts_with(
+                "warmer,run_id=nightly\\ run\\,1\\=a\\\\b,target=https://example.com/a|b#c:d "
+            ),
+            "{}",
+            main
+        );
+        // Tag values contain escaped spaces; fields and timestamp come last
+        let fields = main.rsplit(' ').nth(1).unwrap();
+        for field in [
+            "requests=4i",
+            "failed=1i",
+            "bytes=2048i",
+            "rate=2",
+            "latency_avg_ms=25",
+        ] {
+            assert!(
+                fields.split(',').any(|f| f == field),
+                "{} in {}",
+                field,
+                fields
+            );
+        }
+        let errors = lines.next().unwrap();
+        assert!(errors.starts_with("warmer_errors,run_id=nightly\\ run"));
+        assert!(errors.contains(",category=timeout count=1i "));
+        assert_eq!(lines.next(), None);
+    }
+
+    #[test]
+    fn otlp_payload_has_delta_sums_and_gauges() {
+        let payload = otlp_payload(&tags(), &interval());
+        let resource = &payload["resourceMetrics"][0];
+        let attributes = resource["resource"]["attributes"].as_array().unwrap();
+        assert!(attributes.contains(&json!({
+            "key": "warmer.run_id",
+            "value": {"stringValue": "nightly run,1=a\\b"}
+        })));
+        let metrics = resource["scopeMetrics"][0]["metrics"].as_array().unwrap();
+        let metric = |name: &str| {
+            metrics
+                .iter()
+                .find(|m| m["name"] == name)
+                .unwrap_or_else(|| panic!("no {}", name))
+        };
+
+        let requests = &metric("warmer.requests")["sum"];
+        assert_eq!(requests["aggregationTemporality"], 1);
+        assert_eq!(requests["isMonotonic"], true);
+        let point = &requests["dataPoints"][0];
+        assert_eq!(point["asInt"], "4");
+        let start: i64 = point["startTimeUnixNano"]
+            .as_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        let end: i64 = point["timeUnixNano"].as_str().unwrap().parse().unwrap();
+        assert_eq!(end - start, 2_000_000_000);
+
+        let errors = &metric("warmer.errors")["sum"]["dataPoints"][0];
+        assert_eq!(errors["asInt"], "1");
+        assert_eq!(errors["attributes"][0]["value"]["stringValue"], "timeout");
+        assert_eq!(
+            metric("warmer.latency_avg_ms")["gauge"]["dataPoints"][0]["asDouble"],
+            25.0
+        );
+    }
+
+    #[test]
+    fn sink_parse_picks_the_transport_by_scheme() {
+        assert!(matches!(
+            Sink::parse("statsd://127.0.0.1").unwrap(),
+            Sink::Statsd { addr } if addr == "127.0.0.1:8125"
+        ));
+        assert!(matches!(
+            Sink::parse("otlp+https://collector:4318").unwrap(),
+            Sink::Otlp { url } if url == "https://collector:4318/v1/metrics"
+        ));
+        assert!(matches!(
+            Sink::parse("influx+http://db:8086/write?db=warmer").unwrap(),
+            Sink::Influx { url, .. } if url == "http://db:8086/write?db=warmer"
+        ));
+        assert!(Sink::parse("ftp://example.com").is_err());
+    }
+
+    #[tokio::test]
+    async fn statsd_sink_sends_one_datagram() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let sink = Sink::Statsd {
+            addr: receiver.local_addr().unwrap().to_string(),
+        };
+        sink.push(&tags(), &interval()).await.unwrap();
+
+        let mut buf = vec![0u8; 65536];
+        let n = tokio::time::timeout(Duration::from_secs(5), receiver.recv(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&buf[..n]),
+            statsd_payload(&tags(), &interval())
+        );
+    }
+
+    #[tokio::test]
+    async fn influx_sink_posts_lines_with_the_token() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            // Read until the whole body announced by Content-Length has arrived
+            loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length = head
+                        .lines()
+                        .find_map(|l| {
+                            l.to_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|v| v.trim().to_string())
+                        })
+                        .and_then(|v| v.parse::<usize>().ok())
+                        .unwrap_or(0);
+                    if body.len() >= length || n == 0 {
+                        break;
+                    }
+                }
+            }
+            socket
+                .write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&request).to_string()
+        });
+
+        let sink = Sink::Influx {
+            url: format!("http://{}/write?db=warmer", addr),
+            token: Some("secret".to_string()),
+        };
+        sink.push(&tags(), &interval()).await.unwrap();
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /write?db=warmer HTTP/1.1\r\n"));
+        assert!(
+            request
+                .to_lowercase()
+                .contains("authorization: token secret\r\n")
+        );
+        let body = request.split_once("\r\n\r\n").unwrap().1;
+        assert!(body.starts_with("warmer,run_id=nightly\\ run"));
+        assert!(body.contains("\nwarmer_errors,"));
+    }
+
+    #[tokio::test]
+    async fn http_sink_reports_error_statuses() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 65536];
+            let _ = socket.read(&mut buf).await;
+            let _ = socket
+                .write_all(b"HTTP/1.1 401 Unauthorized\r\nContent-Length: 9\r\n\r\nbad token")
+                .await;
+        });
+
+        let sink = Sink::Otlp {
+            url: format!("http://{}/v1/metrics", addr),
+        };
+        let error = sink.push(&tags(), &interval()).await.unwrap_err();
+        assert_eq!(error, "HTTP 401 Unauthorized bad token");
+    }
+}
@@ -0,0 +1,88 @@
+use crate::interval::{IntervalStats, IntervalTracker};
+use crate::{Stats, dashboard};
+use serde::Deserialize;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+
+/// How much warmer prints while requests run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputLevel {
+    /// Only setup messages and the final summary
+    Quiet,
+    /// A summary line for every --progress-interval
+    Periodic,
+    /// One line per request
+    Requests,
+    /// Per-request lines plus response headers, connection timings and cache status
+    Verbose,
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(OutputLevel::Requests as u8);
+
+pub fn set_level(level: OutputLevel) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn level() -> OutputLevel {
+    match LEVEL.load(Ordering::Relaxed) {
+        0 => OutputLevel::Quiet,
+        1 => OutputLevel::Periodic,
+        3 => OutputLevel::Verbose,
+        _ => OutputLevel::Requests,
+    }
+}
+
+/// Whether one line per request is printed (never while the dashboard is showing).
+pub fn shows_requests() -> bool {
+    level() >= OutputLevel::Requests && !dashboard::is_active()
+}
+
+/// Print one line per `interval` with the rate, latency and errors of that interval.
+pub fn start_periodic(stats: Arc<Mutex<Stats>>, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut tracker = IntervalTracker::new();
+        loop {
+            sleep(interval).await;
+            println!("{}", interval_line(&tracker.next(&stats)));
+        }
+    })
+}
+
+fn interval_line(interval: &IntervalStats) -> String {
+    let secs = interval.elapsed as u64;
+    let mut line = format!(
+        "[{:02}:{:02}:{:02}] {} requests ({:.1}/s)",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        interval.requests,
+        interval.rate()
+    );
+    if let (Some(avg), Some(p50), Some(p95), Some(max)) = (
+        interval.avg(),
+        interval.percentile(50.0),
+        interval.percentile(95.0),
+        interval.max(),
+    ) {
+        line.push_str(&format!(
+            "  avg {:.1} ms  p50 {:.1} ms  p95 {:.1} ms  max {:.1} ms",
+            avg, p50, p95, max
+        ));
+    }
+    line.push_str(&format!("  failed {}", interval.failed));
+
+    // Errors by category that occurred during this interval
+    if !interval.errors.is_empty() {
+        let list: Vec<String> = interval
+            .errors
+            .iter()
+            .map(|(category, n)| format!("{}: {}", category, n))
+            .collect();
+        line.push_str(&format!(" ({})", list.join(", ")));
+    }
+    line
+}
@@ -0,0 +1,223 @@
+use crate::asset_policy::AssetType;
+use crate::histogram::Histogram;
+use crate::{RequestResult, dashboard};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::{Mutex, OnceLock};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use url::Url;
+
+/// Upper bounds of the exported latency buckets, in seconds. Each is a bin edge of the
+/// shared latency histogram, so the cumulative counts are exact.
+const BUCKETS: [f64; 12] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+/// Metrics collected for `--metrics-listen`. Only exists while the exporter runs.
+static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+
+#[derive(Default)]
+struct Registry {
+    /// (status, host, type) -> requests
+    requests: BTreeMap<(String, String, &'static str), u64>,
+    /// type -> latency histogram
+    latency: BTreeMap<&'static str, Histogram>,
+    /// type -> response bytes
+    bytes: BTreeMap<&'static str, u64>,
+    /// error category -> requests that got no response
+    errors: BTreeMap<String, u64>,
+    cache_hits: u64,
+    cache_misses: u64,
+}
+
+/// Record a finished request. Called from `record_result`; a no-op unless
+/// the exporter is running.
+pub fn observe(result: &RequestResult) {
+    let Some(registry) = REGISTRY.get() else {
+        return;
+    };
+    let kind = request_type(result);
+    let host = Url::parse(&result.url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_lowercase()))
+        .unwrap_or_default();
+    let status = match result.status_code {
+        0 => "error".to_string(),
+        code => code.to_string(),
+    };
+    let mut registry = registry.lock().unwrap();
+    *registry.requests.entry((status, host, kind)).or_insert(0) += 1;
+    registry
+        .latency
+        .entry(kind)
+        .or_default()
+        .record(result.response_time);
+    *registry.bytes.entry(kind).or_insert(0) += result.data_size;
+    if let Some(ref error) = result.error {
+        *registry
+            .errors
+            .entry(error.category.to_string())
+            .or_insert(0) += 1;
+    }
+    match result.cache_hit {
+        Some(true) => registry.cache_hits += 1,
+        Some(false) => registry.cache_misses += 1,
+        None => {}
+    }
+}
+
+/// `page` for main URLs, otherwise the asset type guessed from the URL.
+fn request_type(result: &RequestResult) -> &'static str {
+    if result.is_main_url {
+        "page"
+    } else {
+        AssetType::from_url(&result.url).map_or("other", |t| t.name())
+    }
+}
+
+/// Bind `addr` and serve the metrics at `/metrics` in the Prometheus text format
+/// until the process exits.
+pub async fn serve(addr: &str) -> std::io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    let _ = REGISTRY.set(Mutex::new(Registry::default()));
+    println!("Serving Prometheus metrics at http://{}/metrics", addr);
+
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                // The request line is all we need; scrapers send small requests
+                let mut buf = [0u8; 4096];
+                let Ok(n) = socket.read(&mut buf).await else {
+                    return;
+                };
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let path = request.split_whitespace().nth(1).unwrap_or("");
+                let response = if path == "/metrics" || path.starts_with("/metrics?") {
+                    let body = render();
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                } else {
+                    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                        .to_string()
+                };
+                let _ = socket.write_all(response.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            });
+        }
+    });
+    Ok(())
+}
+
+fn render() -> String {
+    let mut out = String::new();
+    let Some(registry) = REGISTRY.get() else {
+        return out;
+    };
+    let registry = registry.lock().unwrap();
+
+    out.push_str("# HELP warmer_requests_total Requests by response status, host and type.\n");
+    out.push_str("# TYPE warmer_requests_total counter\n");
+    for ((status, host, kind), count) in &registry.requests {
+        let _ = writeln!(
+            out,
+            "warmer_requests_total{{status=\"{}\",host=\"{}\",type=\"{}\"}} {}",
+            status,
+            escape(host),
+            kind,
+            count
+        );
+    }
+
+    out.push_str("# HELP warmer_request_duration_seconds Time to response headers, by type.\n");
+    out.push_str("# TYPE warmer_request_duration_seconds histogram\n");
+    for (kind, histogram) in &registry.latency {
+        for bound in BUCKETS {
+            let _ = writeln!(
+                out,
+                "warmer_request_duration_seconds_bucket{{type=\"{}\",le=\"{}\"}} {}",
+                kind,
+                bound,
+                histogram.count_at_most(bound * 1000.0)
+            );
+        }
+        let _ = writeln!(
+            out,
+            "warmer_request_duration_seconds_bucket{{type=\"{}\",le=\"+Inf\"}} {}",
+            kind,
+            histogram.count()
+        );
+        let _ = writeln!(
+            out,
+            "warmer_request_duration_seconds_sum{{type=\"{}\"}} {}",
+            kind,
+            histogram.sum() / 1000.0
+        );
+        let _ = writeln!(
+            out,
+            "warmer_request_duration_seconds_count{{type=\"{}\"}} {}",
+            kind,
+            histogram.count()
+        );
+    }
+
+    out.push_str("# HELP warmer_response_bytes_total Response bytes received, by type.\n");
+    out.push_str("# TYPE warmer_response_bytes_total counter\n");
+    for (kind, bytes) in &registry.bytes {
+        let _ = writeln!(
+            out,
+            "warmer_response_bytes_total{{type=\"{}\"}} {}",
+            kind, bytes
+        );
+    }
+
+    out.push_str("# HELP warmer_request_errors_total Requests that got no response, by cause.\n");
+    out.push_str("# TYPE warmer_request_errors_total counter\n");
+    for (category, count) in &registry.errors {
+        let _ = writeln!(
+            out,
+            "warmer_request_errors_total{{category=\"{}\"}} {}",
+            escape(category),
+            count
+        );
+    }
+
+    out.push_str(
+        "# HELP warmer_cache_responses_total Responses with a cache status header, by result.\n",
+    );
+    out.push_str("# TYPE warmer_cache_responses_total counter\n");
+    let _ = writeln!(
+        out,
+        "warmer_cache_responses_total{{result=\"hit\"}} {}",
+        registry.cache_hits
+    );
+    let _ = writeln!(
+        out,
+        "warmer_cache_responses_total{{result=\"miss\"}} {}",
+        registry.cache_misses
+    );
+
+    let (done, total) = dashboard::progress();
+    out.push_str("# HELP warmer_crawl_pages_done Pages the crawl has finished.\n");
+    out.push_str("# TYPE warmer_crawl_pages_done gauge\n");
+    let _ = writeln!(out, "warmer_crawl_pages_done {}", done);
+    out.push_str("# HELP warmer_crawl_pages_total Pages the crawl will process.\n");
+    out.push_str("# TYPE warmer_crawl_pages_total gauge\n");
+    let _ = writeln!(out, "warmer_crawl_pages_total {}", total);
+    out.push_str("# HELP warmer_active_users Virtual users or crawl workers running.\n");
+    out.push_str("# TYPE warmer_active_users gauge\n");
+    let _ = writeln!(out, "warmer_active_users {}", dashboard::active_users());
+
+    out
+}
+
+/// Escape a label value for the exposition format.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// A token bucket refilled at `rate` tokens per second, holding at most `burst`.
+pub struct TokenBucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(rate: f64, burst: f64) -> Self {
+        let burst = burst.max(1.0);
+        Self {
+            rate,
+            burst,
+            tokens: burst,
+            updated: Instant::now(),
+        }
+    }
+
+    /// Take one token and return how long the caller must wait before using it.
+    /// The balance may go negative, so concurrent callers queue up behind each other.
+    pub fn reserve(&mut self) -> Duration {
+        let now = Instant::now();
+        let refill = now.duration_since(self.updated).as_secs_f64() * self.rate;
+        self.tokens = (self.tokens + refill).min(self.burst);
+        self.updated = now;
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+/// Limits for one kind of request: a bucket shared by all hosts and one bucket per host.
+struct Limit {
+    global: Option<Mutex<TokenBucket>>,
+    per_host: Option<f64>,
+    hosts: Mutex<HashMap<String, TokenBucket>>,
+    burst: f64,
+}
+
+impl Limit {
+    fn new(global: Option<f64>, per_host: Option<f64>, burst: f64) -> Self {
+        let positive = |rate: Option<f64>| rate.filter(|&r| r > 0.0);
+        Self {
+            global: positive(global).map(|r| Mutex::new(TokenBucket::new(r, burst))),
+            per_host: positive(per_host),
+            hosts: Mutex::new(HashMap::new()),
+            burst,
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.global.is_some() || self.per_host.is_some()
+    }
+
+    fn reserve(&self, host: Option<&str>) -> Duration {
+        let global = self
+            .global
+            .as_ref()
+            .map_or(Duration::ZERO, |bucket| bucket.lock().unwrap().reserve());
+        let host_wait = match (self.per_host, host) {
+            (Some(rate), Some(host)) => self
+                .hosts
+                .lock()
+                .unwrap()
+                .entry(host.to_string())
+                .or_insert_with(|| TokenBucket::new(rate, self.burst))
+                .reserve(),
+            _ => Duration::ZERO,
+        };
+        global.max(host_wait)
+    }
+}
+
+/// Requests-per-second limits shared by every task, for page and asset requests
+/// separately. Set with `--page-rate`, `--asset-rate`, `--host-page-rate` and
+/// `--host-asset-rate`; `--host-rps` and a robots.txt Crawl-delay limit all requests
+/// to a host.
+pub struct RateLimits {
+    pages: Limit,
+    assets: Limit,
+    /// Pages and assets alike, per host
+    requests: Limit,
+    /// Host -> one request per Crawl-delay, pages and assets alike
+    crawl_delays: Mutex<HashMap<String, TokenBucket>>,
+}
+
+impl RateLimits {
+    pub fn new(
+        page_rate: Option<f64>,
+        asset_rate: Option<f64>,
+        host_page_rate: Option<f64>,
+        host_asset_rate: Option<f64>,
+        host_rate: Option<f64>,
+        burst: f64,
+    ) -> Self {
+        Self {
+            pages: Limit::new(page_rate, host_page_rate, burst),
+            assets: Limit::new(asset_rate, host_asset_rate, burst),
+            requests: Limit::new(None, host_rate, burst),
+            crawl_delays: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        Self::new(None, None, None, None, None, 1.0)
+    }
+
+    /// Space every request to `host` at least `delay` apart, as robots.txt asked.
+    pub fn add_crawl_delay(&self, host: &str, delay: Duration) {
+        if delay.is_zero() {
+            return;
+        }
+        self.crawl_delays.lock().unwrap().insert(
+            host.to_lowercase(),
+            TokenBucket::new(1.0 / delay.as_secs_f64(), 1.0),
+        );
+    }
+
+    /// Reserve a request slot for `url` and return how long to wait before sending it.
+    pub fn reserve(&self, url: &str, is_page: bool) -> Duration {
+        let limit = if is_page { &self.pages } else { &self.assets };
+        let mut crawl_delays = self.crawl_delays.lock().unwrap();
+        if !limit.is_active() && !self.requests.is_active() && crawl_delays.is_empty() {
+            return Duration::ZERO;
+        }
+        let host = Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_lowercase()));
+        let crawl_delay = host
+            .as_deref()
+            .and_then(|host| crawl_delays.get_mut(host))
+            .map_or(Duration::ZERO, |bucket| bucket.reserve());
+        drop(crawl_delays);
+        let wait = [limit, &self.requests]
+            .into_iter()
+            .filter(|limit| limit.is_active())
+            .map(|limit| limit.reserve(host.as_deref()))
+            .max()
+            .unwrap_or(Duration::ZERO);
+        wait.max(crawl_delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Duration, b: Duration) -> bool {
+        a.abs_diff(b) < Duration::from_millis(20)
+    }
+
+    #[test]
+    fn bucket_allows_a_burst_then_spaces_requests() {
+        let mut bucket = TokenBucket::new(10.0, 3.0);
+        for _ in 0..3 {
+            assert_eq!(bucket.reserve(), Duration::ZERO);
+        }
+        assert!(close(bucket.reserve(), Duration::from_millis(100)));
+        assert!(close(bucket.reserve(), Duration::from_millis(200)));
+    }
+
+    #[test]
+    fn bucket_burst_is_at_least_one() {
+        let mut bucket = TokenBucket::new(2.0, 0.0);
+        assert_eq!(bucket.reserve(), Duration::ZERO);
+        assert!(close(bucket.reserve(), Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn unlimited_never_waits() {
+        let limits = RateLimits::unlimited();
+        for _ in 0..100 {
+            assert_eq!(limits.reserve("https://example.com/", true), Duration::ZERO);
+        }
+    }
+
+    #[test]
+    fn pages_and_assets_are_limited_separately() {
+        let limits = RateLimits::new(Some(1.0), None, None, None, None, 1.0);
+        assert_eq!(
+            limits.reserve("https://example.com/a", true),
+            Duration::ZERO
+        );
+        assert!(close(
+            limits.reserve("https://example.com/b", true),
+            Duration::from_secs(1)
+        ));
+        assert_eq!(
+            limits.reserve("https://example.com/a.css", false),
+            Duration::ZERO
+        );
+        assert_eq!(
+            limits.reserve("https://example.com/b.css", false),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn per_host_limits_keep_hosts_apart() {
+        let limits = RateLimits::new(None, None, Some(1.0), None, None, 1.0);
+        assert_eq!(limits.reserve("https://a.example/", true), Duration::ZERO);
+        assert_eq!(limits.reserve("https://B.example/", true), Duration::ZERO);
+        assert!(close(
+            limits.reserve("https://a.example/x", true),
+            Duration::from_secs(1)
+        ));
+        assert!(close(
+            limits.reserve("https://b.example/x", true),
+            Duration::from_secs(1)
+        ));
+    }
+
+    #[test]
+    fn host_rate_counts_pages_and_assets_together() {
+        let limits = RateLimits::new(None, None, None, None, Some(2.0), 1.0);
+        assert_eq!(limits.reserve("https://example.com/", true), Duration::ZERO);
+        assert!(close(
+            limits.reserve("https://example.com/a.css", false),
+            Duration::from_millis(500)
+        ));
+        assert_eq!(
+            limits.reserve("https://other.example/", false),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn crawl_delay_spaces_pages_and_assets_on_its_host() {
+        let limits = RateLimits::unlimited();
+        limits.add_crawl_delay("Example.com", Duration::from_secs(2));
+        assert_eq!(limits.reserve("https://example.com/", true), Duration::ZERO);
+        assert!(close(
+            limits.reserve("https://example.com/a.css", false),
+            Duration::from_secs(2)
+        ));
+        assert!(close(
+            limits.reserve("https://example.com/b", true),
+            Duration::from_secs(4)
+        ));
+        assert_eq!(
+            limits.reserve("https://other.example/", true),
+            Duration::ZERO
+        );
+    }
+}
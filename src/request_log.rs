@@ -0,0 +1,272 @@
+use crate::RequestResult;
+use chrono::SecondsFormat;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::future::Future;
+use std::sync::OnceLock;
+use tokio::fs::File;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::mpsc::{Sender, channel};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+/// File format of `--log-requests`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogFormat {
+    Csv,
+    Jsonl,
+}
+
+impl LogFormat {
+    /// CSV for `.csv` paths, JSON lines for anything else.
+    pub fn from_path(path: &str) -> Self {
+        if path.to_lowercase().ends_with(".csv") {
+            Self::Csv
+        } else {
+            Self::Jsonl
+        }
+    }
+}
+
+/// Entries waiting to be written. When the writer falls this far behind, requests
+/// wait for it rather than buffering without bound.
+const QUEUE_CAPACITY: usize = 8192;
+
+/// Column order of the CSV file; JSON lines use the same field names.
+const COLUMNS: [&str; 15] = [
+    "timestamp",
+    "url",
+    "final_url",
+    "is_main_url",
+    "user",
+    "status",
+    "http_version",
+    "bytes",
+    "response_ms",
+    "dns_ms",
+    "connect_ms",
+    "tls_ms",
+    "first_byte_ms",
+    "cache",
+    "error",
+];
+
+tokio::task_local! {
+    /// Virtual user (load mode) or crawl worker the current task makes requests for.
+    static VIRTUAL_USER: usize;
+}
+
+/// Run `future` with its requests logged as made by virtual user `user`.
+pub async fn as_user<F: Future>(user: usize, future: F) -> F::Output {
+    VIRTUAL_USER.scope(user, future).await
+}
+
+/// The virtual user of the current task, if any.
+pub fn current_user() -> Option<usize> {
+    VIRTUAL_USER.try_with(|user| *user).ok()
+}
+
+/// `tokio::spawn` for tasks a virtual user starts, so their requests are logged
+/// under the same user.
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    match current_user() {
+        Some(user) => tokio::spawn(VIRTUAL_USER.scope(user, future)),
+        None => tokio::spawn(future),
+    }
+}
+
+enum Message {
+    Entry(Box<Entry>),
+    /// Flush the file, stop writing and acknowledge
+    Close(oneshot::Sender<()>),
+}
+
+/// Set while `--log-requests` is writing. Requests only pay for building an entry,
+/// outside the stats lock, and a channel send; the file is written by a separate task.
+static SENDER: OnceLock<Sender<Message>> = OnceLock::new();
+
+pub fn is_active() -> bool {
+    SENDER.get().is_some()
+}
+
+/// One row of the log.
+#[derive(Serialize)]
+struct Entry {
+    timestamp: String,
+    url: String,
+    /// Where redirects ended up; None when the URL answered itself
+    final_url: Option<String>,
+    is_main_url: bool,
+    user: Option<usize>,
+    /// 0 when no response arrived
+    status: u16,
+    http_version: String,
+    bytes: u64,
+    response_ms: f64,
+    dns_ms: Option<f64>,
+    connect_ms: Option<f64>,
+    tls_ms: Option<f64>,
+    first_byte_ms: Option<f64>,
+    /// "hit" or "miss"; None without cache status headers
+    cache: Option<&'static str>,
+    /// "<category>: <message>" for requests that got no response
+    error: Option<String>,
+}
+
+/// Create `path` and start writing one entry per finished request to it.
+pub async fn open(path: &str, format: LogFormat) -> std::io::Result<()> {
+    let mut file = BufWriter::new(File::create(path).await?);
+    if format == LogFormat::Csv {
+        file.write_all(format!("{}\n", COLUMNS.join(",")).as_bytes())
+            .await?;
+    }
+
+    let (sender, mut receiver) = channel(QUEUE_CAPACITY);
+    tokio::spawn(async move {
+        while let Some(message) = receiver.recv().await {
+            match message {
+                Message::Entry(entry) => {
+                    let line = match format {
+                        LogFormat::Csv => csv_line(&entry),
+                        LogFormat::Jsonl => serde_json::to_string(&entry).unwrap_or_default(),
+                    };
+                    if let Err(e) = file.write_all(format!("{}\n", line).as_bytes()).await {
+                        eprintln!("Cannot write request log: {}", e);
+                        return;
+                    }
+                }
+                Message::Close(done) => {
+                    if let Err(e) = file.flush().await {
+                        eprintln!("Cannot write request log: {}", e);
+                    }
+                    let _ = done.send(());
+                    return;
+                }
+            }
+        }
+    });
+    let _ = SENDER.set(sender);
+    Ok(())
+}
+
+/// Log a finished request. Called from `record_result` outside the stats lock; a
+/// no-op unless `--log-requests` is set.
+pub fn record(result: &RequestResult) {
+    let Some(sender) = SENDER.get() else {
+        return;
+    };
+    let timings = result.timings;
+    // Microsecond precision is plenty and keeps float noise out of the file
+    let ms = |value: f64| (value * 1000.0).round() / 1000.0;
+    let entry = Entry {
+        timestamp: result
+            .started_at
+            .to_rfc3339_opts(SecondsFormat::Millis, true),
+        url: result.url.clone(),
+        final_url: result.redirects.last().map(|hop| hop.location.clone()),
+        is_main_url: result.is_main_url,
+        user: current_user(),
+        status: result.status_code,
+        http_version: result.http_version.clone(),
+        bytes: result.data_size,
+        response_ms: result.response_time,
+        dns_ms: timings.map(|t| ms(t.dns)),
+        connect_ms: timings.map(|t| ms(t.connect)),
+        tls_ms: timings.map(|t| ms(t.tls)),
+        // libcurl has not always filled this in yet when the headers arrive
+        first_byte_ms: timings.map(|t| ms(t.first_byte)).filter(|&v| v > 0.0),
+        cache: result.cache_hit.map(|hit| if hit { "hit" } else { "miss" }),
+        error: result
+            .error
+            .as_ref()
+            .map(|e| format!("{}: {}", e.category.tag(), e.message)),
+    };
+    if let Err(TrySendError::Full(message)) = sender.try_send(Message::Entry(Box::new(entry))) {
+        // Callers are runtime tasks and the JS crawler's plain threads; either may block
+        let _ = tokio::task::block_in_place(|| sender.blocking_send(message));
+    }
+}
+
+/// Write out everything logged so far. Requests finishing later are not logged.
+pub async fn close() {
+    let Some(sender) = SENDER.get() else {
+        return;
+    };
+    let (done, flushed) = oneshot::channel();
+    if sender.send(Message::Close(done)).await.is_ok() {
+        let _ = flushed.await;
+    }
+}
+
+fn csv_line(entry: &Entry) -> String {
+    let Ok(Value::Object(fields)) = serde_json::to_value(entry) else {
+        return String::new();
+    };
+    COLUMNS
+        .iter()
+        .map(|column| match fields.get(*column) {
+            None | Some(Value::Null) => String::new(),
+            Some(Value::String(s)) if s.contains([',', '"', '\n', '\r']) => {
+                format!("\"{}\"", s.replace('"', "\"\""))
+            }
+            Some(Value::String(s)) => s.clone(),
+            Some(value) => value.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(url: &str, error: Option<&str>) -> Entry {
+        Entry {
+            timestamp: "2026-01-01T00:00:00.000Z".to_string(),
+            url: url.to_string(),
+            final_url: None,
+            is_main_url: true,
+            user: Some(3),
+            status: 200,
+            http_version: "HTTP/2.0".to_string(),
+            bytes: 512,
+            response_ms: 12.5,
+            dns_ms: None,
+            connect_ms: Some(1.25),
+            tls_ms: None,
+            first_byte_ms: None,
+            cache: Some("hit"),
+            error: error.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn csv_line_follows_the_columns() {
+        assert_eq!(
+            csv_line(&entry("https://example.com/", None)),
+            "2026-01-01T00:00:00.000Z,https://example.com/,,true,3,200,HTTP/2.0,512,12.5,,1.25,,,hit,"
+        );
+    }
+
+    #[test]
+    fn csv_line_quotes_commas_quotes_and_newlines() {
+        let line = csv_line(&entry(
+            "https://example.com/a,b",
+            Some("protocol: bad \"header\"\nsecond line"),
+        ));
+        assert!(line.contains(",\"https://example.com/a,b\","), "{}", line);
+        assert!(
+            line.ends_with(",hit,\"protocol: bad \"\"header\"\"\nsecond line\""),
+            "{}",
+            line
+        );
+        let line = csv_line(&entry("https://example.com/\r", None));
+        assert!(line.contains(",\"https://example.com/\r\","), "{}", line);
+    }
+}
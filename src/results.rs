@@ -0,0 +1,188 @@
+use crate::histogram::Histogram;
+use crate::{Stats, UrlStats};
+use chrono::SecondsFormat;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::time::Duration;
+
+/// Version of the `--report-json` layout, bumped when fields change meaning.
+const FORMAT_VERSION: u32 = 1;
+
+/// Latency figures in ms.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Latency {
+    pub avg: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+impl Latency {
+    fn of(histogram: &Histogram) -> Self {
+        let percentile = |p: f64| histogram.percentile(p).unwrap_or(0.0);
+        Self {
+            avg: histogram.avg().unwrap_or(0.0),
+            p50: percentile(50.0),
+            p90: percentile(90.0),
+            p95: percentile(95.0),
+            p99: percentile(99.0),
+            max: histogram.max().unwrap_or(0.0),
+        }
+    }
+}
+
+/// Numbers for one requested URL.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UrlResults {
+    pub is_main_url: bool,
+    pub requests: usize,
+    pub failed: usize,
+    pub bytes: u64,
+    pub latency: Latency,
+}
+
+impl UrlResults {
+    fn of(stats: &UrlStats) -> Self {
+        Self {
+            is_main_url: stats.is_main_url,
+            requests: stats.requests,
+            failed: stats.failed,
+            bytes: stats.bytes,
+            latency: Latency::of(&stats.latency),
+        }
+    }
+}
+
+/// Requests that finished during one bucket of the run. Latencies are estimated
+/// from a histogram and missing for buckets without requests.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IntervalResults {
+    /// Start of the bucket, from the start of the run
+    pub offset_secs: f64,
+    pub requests: usize,
+    pub failed: usize,
+    pub errors: usize,
+    pub bytes: u64,
+    pub rps: f64,
+    pub error_rate: Option<f64>,
+    pub avg_ms: Option<f64>,
+    pub p50_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+    pub p99_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+}
+
+/// The outcome of a run as written by `--report-json` and read back by `--compare`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunResults {
+    pub version: u32,
+    pub target: String,
+    pub started_at: String,
+    pub elapsed_secs: f64,
+    pub requests: usize,
+    pub successful: usize,
+    pub failed: usize,
+    /// Percent of requests that succeeded
+    pub availability: f64,
+    /// Percent of requests that failed
+    pub error_rate: f64,
+    /// Requests per second
+    pub rps: f64,
+    pub bytes: u64,
+    pub latency: Latency,
+    pub status_codes: BTreeMap<u16, usize>,
+    /// Requests that got no response, by error category
+    pub errors: BTreeMap<String, usize>,
+    pub cache_hits: usize,
+    pub cache_misses: usize,
+    /// Requested URL -> its own numbers
+    pub urls: BTreeMap<String, UrlResults>,
+    /// Width of the buckets in `intervals`
+    #[serde(default)]
+    pub interval_secs: f64,
+    #[serde(default)]
+    pub intervals: Vec<IntervalResults>,
+}
+
+impl RunResults {
+    pub fn of(stats: &Stats, target: &str) -> Self {
+        let error_rate = if stats.transactions == 0 {
+            0.0
+        } else {
+            stats.failed_transactions as f64 / stats.transactions as f64 * 100.0
+        };
+        Self {
+            version: FORMAT_VERSION,
+            target: target.to_string(),
+            started_at: stats.started_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+            elapsed_secs: stats.elapsed_time(),
+            requests: stats.transactions,
+            successful: stats.successful_transactions,
+            failed: stats.failed_transactions,
+            availability: stats.availability(),
+            error_rate,
+            rps: stats.transaction_rate(),
+            bytes: stats.data_transferred,
+            latency: Latency::of(&stats.latency),
+            status_codes: stats.status_codes.iter().map(|(k, v)| (*k, *v)).collect(),
+            errors: stats
+                .errors
+                .iter()
+                .map(|(category, count)| (category.tag().to_string(), *count))
+                .collect(),
+            cache_hits: stats.cache_hits,
+            cache_misses: stats.cache_misses,
+            urls: stats
+                .urls
+                .iter()
+                .map(|(url, url_stats)| (url.clone(), UrlResults::of(url_stats)))
+                .collect(),
+            interval_secs: stats.timeline.width().as_secs_f64(),
+            intervals: stats
+                .timeline
+                .intervals(Duration::from_secs_f64(stats.elapsed_time()))
+                .map(|(offset, length, bucket)| IntervalResults {
+                    offset_secs: offset.as_secs_f64(),
+                    requests: bucket.requests,
+                    failed: bucket.failed,
+                    errors: bucket.errors,
+                    bytes: bucket.bytes,
+                    rps: if length.is_zero() {
+                        0.0
+                    } else {
+                        bucket.requests as f64 / length.as_secs_f64()
+                    },
+                    error_rate: bucket.error_rate(),
+                    avg_ms: bucket.latency.avg(),
+                    p50_ms: bucket.latency.percentile(50.0),
+                    p95_ms: bucket.latency.percentile(95.0),
+                    p99_ms: bucket.latency.percentile(99.0),
+                    max_ms: bucket.latency.max(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, json + "\n")
+            .map_err(|e| format!("Cannot write JSON report {}: {}", path, e))
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let json =
+            fs::read_to_string(path).map_err(|e| format!("Cannot read results {}: {}", path, e))?;
+        let results: Self = serde_json::from_str(&json)
+            .map_err(|e| format!("Cannot parse results {}: {}", path, e))?;
+        if results.version != FORMAT_VERSION {
+            return Err(format!(
+                "Results {} have format version {}, expected {}",
+                path, results.version, FORMAT_VERSION
+            ));
+        }
+        Ok(results)
+    }
+}
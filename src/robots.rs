@@ -0,0 +1,466 @@
+use crate::{FORCE_HTTP1, http_client, rate_limits};
+use isahc::config::VersionNegotiation;
+use isahc::{Request, prelude::*};
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use url::Url;
+
+/// The name robots.txt groups address warmer by, whichever User-Agent header it sends.
+const PRODUCT_TOKEN: &str = "warmer";
+
+/// Longest Crawl-delay obeyed; larger values would stall the crawl indefinitely.
+const MAX_CRAWL_DELAY: Duration = Duration::from_secs(60);
+
+/// robots.txt rules for every host the follow-links and JS crawlers reach, fetched the
+/// first time a URL on the host is checked. A host's Crawl-delay is registered with
+/// `RateLimits` when its robots.txt is fetched.
+pub struct Robots {
+    user_agent: String,
+    /// False with `--ignore-robots` or outside the crawl modes: everything is allowed
+    enabled: bool,
+    /// Origin -> its rules, filled once per origin while other origins stay usable
+    origins: Mutex<HashMap<String, Arc<OnceLock<RobotsRules>>>>,
+}
+
+impl Robots {
+    pub fn new(user_agent: &str, enabled: bool) -> Self {
+        Self {
+            user_agent: user_agent.to_string(),
+            enabled,
+            origins: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether `url` may be fetched under the robots.txt of its own host.
+    pub fn is_allowed(&self, url: &str) -> bool {
+        if !self.enabled {
+            return true;
+        }
+        let Ok(parsed) = Url::parse(url) else {
+            return true;
+        };
+        if parsed.host_str().is_none() {
+            return true;
+        }
+        let origin = parsed.origin().ascii_serialization();
+        let cell = self
+            .origins
+            .lock()
+            .unwrap()
+            .entry(origin.clone())
+            .or_default()
+            .clone();
+        cell.get_or_init(|| {
+            // A blocking request, as the JS crawler's threads have no runtime; on a
+            // runtime worker, let other tasks move off this thread meanwhile
+            let rules = tokio::task::block_in_place(|| {
+                fetch(
+                    &origin,
+                    parsed.host_str().unwrap_or_default(),
+                    &self.user_agent,
+                )
+            });
+            if let (Some(host), Some(delay)) = (rules.host(), rules.crawl_delay()) {
+                rate_limits().add_crawl_delay(host, delay);
+            }
+            rules
+        })
+        .is_allowed(url)
+    }
+}
+
+/// Fetch robots.txt for `origin` and keep the rules for our User-Agent. A missing or
+/// unreachable robots.txt allows everything.
+fn fetch(origin: &str, host: &str, user_agent: &str) -> RobotsRules {
+    let robots_url = format!("{}/robots.txt", origin);
+
+    let mut builder = Request::get(&robots_url).header("User-Agent", user_agent);
+    if FORCE_HTTP1.load(Ordering::Relaxed) {
+        builder = builder.version_negotiation(VersionNegotiation::http11());
+    }
+    let Ok(request) = builder.body(()) else {
+        return RobotsRules::allow_all();
+    };
+
+    let content = match http_client().send(request) {
+        Ok(mut response) if response.status().is_success() => response.text().unwrap_or_default(),
+        _ => {
+            println!(
+                "No robots.txt rules at {}, crawling without restrictions",
+                robots_url
+            );
+            return RobotsRules::allow_all();
+        }
+    };
+
+    let rules = RobotsRules::parse(&content, host);
+    match rules.crawl_delay() {
+        Some(delay) => println!(
+            "Obeying robots.txt at {} (Crawl-delay: {:.1}s)",
+            robots_url,
+            delay.as_secs_f64()
+        ),
+        None => println!("Obeying robots.txt at {}", robots_url),
+    }
+    rules
+}
+
+/// A single Allow/Disallow line from robots.txt.
+#[derive(Clone, Debug)]
+struct Rule {
+    pattern: String,
+    allow: bool,
+}
+
+/// A `User-agent:` group: the agent tokens it applies to and its rules.
+#[derive(Default, Debug)]
+struct Group {
+    agents: Vec<String>,
+    rules: Vec<Rule>,
+    crawl_delay: Option<Duration>,
+}
+
+/// robots.txt rules of one host that apply to our User-Agent. An empty rule set (no
+/// robots.txt) allows everything. Rules only apply to URLs on the host the robots.txt
+/// was fetched from.
+#[derive(Debug)]
+pub struct RobotsRules {
+    host: Option<String>,
+    rules: Vec<Rule>,
+    crawl_delay: Option<Duration>,
+}
+
+impl RobotsRules {
+    /// Rules that allow every URL and impose no delay.
+    pub fn allow_all() -> Self {
+        Self {
+            host: None,
+            rules: Vec::new(),
+            crawl_delay: None,
+        }
+    }
+
+    /// Parse robots.txt content, keeping the groups for warmer's product token. A group
+    /// name matches when it equals the token, ignoring case and any `/version`;
+    /// otherwise the `*` groups apply.
+    pub fn parse(content: &str, host: &str) -> Self {
+        let mut groups: Vec<Group> = Vec::new();
+        let mut in_agent_lines = false;
+
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let key = key.trim().to_lowercase();
+            let value = value.trim();
+
+            match key.as_str() {
+                "user-agent" => {
+                    // Consecutive User-agent lines share one group
+                    if !in_agent_lines || groups.is_empty() {
+                        groups.push(Group::default());
+                    }
+                    in_agent_lines = true;
+                    if let Some(group) = groups.last_mut() {
+                        group.agents.push(value.to_lowercase());
+                    }
+                }
+                "allow" | "disallow" => {
+                    in_agent_lines = false;
+                    // An empty Disallow means "allow everything" and adds no rule
+                    if value.is_empty() {
+                        continue;
+                    }
+                    if let Some(group) = groups.last_mut() {
+                        group.rules.push(Rule {
+                            pattern: value.to_string(),
+                            allow: key == "allow",
+                        });
+                    }
+                }
+                "crawl-delay" => {
+                    in_agent_lines = false;
+                    if let (Some(group), Some(delay)) = (groups.last_mut(), parse_delay(value)) {
+                        group.crawl_delay = Some(delay);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let names_us = |agent: &str| agent.split('/').next() == Some(PRODUCT_TOKEN);
+        let named = groups
+            .iter()
+            .any(|g| g.agents.iter().any(|agent| names_us(agent)));
+        let applies = |agent: &str| {
+            if named { names_us(agent) } else { agent == "*" }
+        };
+
+        let mut rules = Vec::new();
+        let mut crawl_delay = None;
+        for group in groups
+            .iter()
+            .filter(|g| g.agents.iter().any(|a| applies(a)))
+        {
+            rules.extend(group.rules.iter().cloned());
+            crawl_delay = crawl_delay.or(group.crawl_delay);
+        }
+
+        Self {
+            host: Some(host.to_string()),
+            rules,
+            crawl_delay,
+        }
+    }
+
+    /// Whether `url` may be fetched. The longest matching pattern decides;
+    /// on a tie between Allow and Disallow, Allow wins.
+    pub fn is_allowed(&self, url: &str) -> bool {
+        if self.rules.is_empty() {
+            return true;
+        }
+        let Ok(parsed) = Url::parse(url) else {
+            return true;
+        };
+        if parsed.host_str() != self.host.as_deref() {
+            return true;
+        }
+        let mut path = parsed.path().to_string();
+        if let Some(query) = parsed.query() {
+            path.push('?');
+            path.push_str(query);
+        }
+
+        let mut best: Option<&Rule> = None;
+        for rule in &self.rules {
+            if !pattern_matches(&rule.pattern, &path) {
+                continue;
+            }
+            best = match best {
+                Some(current)
+                    if current.pattern.len() > rule.pattern.len()
+                        || (current.pattern.len() == rule.pattern.len() && current.allow) =>
+                {
+                    Some(current)
+                }
+                _ => Some(rule),
+            };
+        }
+        best.is_none_or(|rule| rule.allow)
+    }
+
+    /// The host the rules were fetched for.
+    pub fn host(&self) -> Option<&str> {
+        self.host.as_deref()
+    }
+
+    /// Crawl-delay for our User-Agent. Enforced by `RateLimits` on every request to
+    /// the host, not only while discovering pages.
+    pub fn crawl_delay(&self) -> Option<Duration> {
+        self.crawl_delay
+    }
+}
+
+/// A Crawl-delay value in seconds; None for zero, negative, non-finite or
+/// unparseable values. Capped at `MAX_CRAWL_DELAY`.
+fn parse_delay(value: &str) -> Option<Duration> {
+    let seconds = value
+        .parse::<f64>()
+        .ok()
+        .filter(|s| s.is_finite() && *s > 0.0)?;
+    Duration::try_from_secs_f64(seconds.min(MAX_CRAWL_DELAY.as_secs_f64())).ok()
+}
+
+/// Match a robots.txt path pattern, supporting `*` wildcards and a trailing `$` anchor.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(p) => (p, true),
+        None => (pattern, false),
+    };
+    let parts: Vec<&str> = pattern.split('*').collect();
+
+    let Some(rest) = path.strip_prefix(parts[0]) else {
+        return false;
+    };
+    let mut rest = rest;
+    for (i, part) in parts.iter().enumerate().skip(1) {
+        let is_last = i == parts.len() - 1;
+        if is_last && anchored {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UA: &str = "Mozilla/5.0 (compatible; warmer/1.0)";
+
+    /// Serve `robots` as robots.txt for one request and return the origin.
+    fn serve_robots(robots: &'static str) -> String {
+        use std::io::{Read, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let origin = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request);
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                robots.len(),
+                robots
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        origin
+    }
+
+    #[test]
+    fn each_host_is_checked_against_its_own_robots_txt() {
+        let first = serve_robots("User-agent: *\nDisallow: /a\n");
+        let second = serve_robots("User-agent: *\nDisallow: /b\n");
+        let robots = Robots::new(UA, true);
+        assert!(!robots.is_allowed(&format!("{}/a", first)));
+        assert!(robots.is_allowed(&format!("{}/b", first)));
+        assert!(robots.is_allowed(&format!("{}/a", second)));
+        assert!(!robots.is_allowed(&format!("{}/b/page", second)));
+        // Fetched once per origin; the listeners only answer one request
+        assert!(!robots.is_allowed(&format!("{}/a/again", first)));
+    }
+
+    #[test]
+    fn disabled_robots_allow_everything() {
+        let robots = Robots::new(UA, false);
+        assert!(robots.is_allowed("http://127.0.0.1:9/private"));
+    }
+
+    #[test]
+    fn wildcard_matches_any_run_of_characters() {
+        assert!(pattern_matches("/private*", "/private/a"));
+        assert!(pattern_matches("/*.php", "/index.php?x=1"));
+        assert!(pattern_matches("/a*b*c", "/a-x-b-y-c-z"));
+        assert!(!pattern_matches("/a*b*c", "/a-x-c-y-b"));
+        assert!(!pattern_matches("/private*", "/public"));
+    }
+
+    #[test]
+    fn dollar_anchors_the_end_of_the_path() {
+        assert!(pattern_matches("/*.pdf$", "/docs/file.pdf"));
+        assert!(!pattern_matches("/*.pdf$", "/docs/file.pdf?download=1"));
+        assert!(pattern_matches("/exact$", "/exact"));
+        assert!(!pattern_matches("/exact$", "/exactly"));
+    }
+
+    #[test]
+    fn plain_patterns_are_prefixes() {
+        assert!(pattern_matches("/shop", "/shop/cart"));
+        assert!(pattern_matches("/", "/anything"));
+        assert!(!pattern_matches("/shop", "/about/shop"));
+    }
+
+    #[test]
+    fn longest_matching_pattern_wins() {
+        let rules = RobotsRules::parse(
+            "User-agent: *\nDisallow: /shop\nAllow: /shop/public\nDisallow: /shop/public/drafts\n",
+            "example.com",
+        );
+        assert!(!rules.is_allowed("https://example.com/shop/cart"));
+        assert!(rules.is_allowed("https://example.com/shop/public/item"));
+        assert!(!rules.is_allowed("https://example.com/shop/public/drafts/1"));
+        assert!(rules.is_allowed("https://example.com/about"));
+    }
+
+    #[test]
+    fn allow_wins_a_tie_with_disallow() {
+        for content in [
+            "User-agent: *\nDisallow: /page\nAllow: /page\n",
+            "User-agent: *\nAllow: /page\nDisallow: /page\n",
+        ] {
+            let rules = RobotsRules::parse(content, "example.com");
+            assert!(rules.is_allowed("https://example.com/page"));
+        }
+    }
+
+    #[test]
+    fn rules_only_apply_to_their_own_host() {
+        let rules = RobotsRules::parse("User-agent: *\nDisallow: /\n", "example.com");
+        assert!(!rules.is_allowed("https://example.com/a"));
+        assert!(rules.is_allowed("https://cdn.example.net/a"));
+    }
+
+    #[test]
+    fn warmer_group_is_used_over_the_wildcard() {
+        let content = "\
+User-agent: *
+Disallow: /all
+
+User-agent: Warmer
+Disallow: /warmer-only
+Crawl-delay: 2
+
+User-agent: other
+Disallow: /other
+";
+        let rules = RobotsRules::parse(content, "example.com");
+        assert!(rules.is_allowed("https://example.com/all"));
+        assert!(!rules.is_allowed("https://example.com/warmer-only"));
+        assert!(rules.is_allowed("https://example.com/other"));
+        assert_eq!(rules.crawl_delay(), Some(Duration::from_secs(2)));
+
+        let rules = RobotsRules::parse("User-agent: warmer/1.0\nDisallow: /v\n", "example.com");
+        assert!(!rules.is_allowed("https://example.com/v"));
+    }
+
+    #[test]
+    fn other_crawlers_groups_do_not_apply() {
+        // Names that merely contain, or are contained in, warmer's User-Agent
+        let content = "\
+User-agent: mozilla
+User-agent: bot
+Disallow: /mozilla
+
+User-agent: warmerbot
+Disallow: /warmerbot
+
+User-agent: *
+Disallow: /all
+Crawl-delay: 1
+";
+        let rules = RobotsRules::parse(content, "example.com");
+        assert!(rules.is_allowed("https://example.com/mozilla"));
+        assert!(rules.is_allowed("https://example.com/warmerbot"));
+        assert!(!rules.is_allowed("https://example.com/all"));
+        assert_eq!(rules.crawl_delay(), Some(Duration::from_secs(1)));
+
+        let rules = RobotsRules::parse("User-agent: curl\nDisallow: /\n", "example.com");
+        assert!(rules.is_allowed("https://example.com/"));
+    }
+
+    #[test]
+    fn consecutive_user_agent_lines_share_a_group() {
+        let content = "User-agent: other\nUser-agent: warmer\nDisallow: /x\n";
+        let rules = RobotsRules::parse(content, "example.com");
+        assert!(!rules.is_allowed("https://example.com/x"));
+    }
+
+    #[test]
+    fn crawl_delay_rejects_bad_values_and_is_capped() {
+        assert_eq!(parse_delay("1.5"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_delay("3600"), Some(MAX_CRAWL_DELAY));
+        assert_eq!(parse_delay("1e30"), Some(MAX_CRAWL_DELAY));
+        for bad in ["inf", "-inf", "NaN", "0", "-1", "soon", ""] {
+            assert_eq!(parse_delay(bad), None, "{}", bad);
+        }
+        let rules = RobotsRules::parse("User-agent: *\nCrawl-delay: inf\n", "example.com");
+        assert_eq!(rules.crawl_delay(), None);
+    }
+}
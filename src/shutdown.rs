@@ -0,0 +1,69 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tokio::time::sleep;
+
+/// Set by the first Ctrl-C. Workers stop issuing new requests once it is set.
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+static NOTIFY: Notify = Notify::const_new();
+
+/// Ask every worker to stop. Returns true if a shutdown was already requested,
+/// i.e. this is the second Ctrl-C.
+pub fn request() -> bool {
+    let already = REQUESTED.swap(true, Ordering::SeqCst);
+    NOTIFY.notify_waiters();
+    already
+}
+
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Resolves once a shutdown has been requested.
+pub async fn wait() {
+    loop {
+        // Register before checking the flag, so a request in between still wakes us
+        let notified = NOTIFY.notified();
+        if requested() {
+            return;
+        }
+        notified.await;
+    }
+}
+
+/// Sleep for `duration`, returning early (with false) if a shutdown is requested.
+pub async fn sleep_unless_stopped(duration: Duration) -> bool {
+    tokio::select! {
+        _ = sleep(duration) => true,
+        _ = wait() => false,
+    }
+}
+
+/// `sleep_unless_stopped` for plain threads, which check for a shutdown request every
+/// 100 ms. Returns false at once when a shutdown was already requested.
+pub fn sleep_blocking_unless_stopped(duration: Duration) -> bool {
+    let deadline = Instant::now() + duration;
+    loop {
+        if requested() {
+            return false;
+        }
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return true;
+        }
+        std::thread::sleep(left.min(Duration::from_millis(100)));
+    }
+}
+
+/// Run `work` to completion, or until `grace` has passed after a shutdown request.
+/// Returns None when in-flight work was abandoned.
+pub async fn with_grace<T>(work: impl Future<Output = T>, grace: Duration) -> Option<T> {
+    tokio::select! {
+        result = work => Some(result),
+        _ = async {
+            wait().await;
+            sleep(grace).await;
+        } => None,
+    }
+}
@@ -0,0 +1,178 @@
+use crate::asset_policy::AssetType;
+use xml::reader::{EventReader, XmlEvent};
+
+const IMAGE_NS: &str = "http://www.google.com/schemas/sitemap-image/1.1";
+const VIDEO_NS: &str = "http://www.google.com/schemas/sitemap-video/1.1";
+
+/// Media attached to one sitemap `<url>` entry.
+pub struct PageMedia {
+    /// The entry's `<loc>` page URL
+    pub page: String,
+    pub assets: Vec<(String, AssetType)>,
+}
+
+/// Collect Google image and video sitemap extension URLs (`image:loc`,
+/// `video:thumbnail_loc`, `video:content_loc`) per `<url>` entry.
+///
+/// Parsed as an event stream rather than through `UrlSet`, because the extension
+/// elements may be interleaved in any order, which serde-xml-rs sequences reject.
+/// Elements are matched by namespace URI, so any prefix works, and `<loc>` elements
+/// of other namespaces are ignored. Malformed XML ends the
+/// scan and keeps whatever was collected so far.
+pub fn parse(content: &str) -> Vec<PageMedia> {
+    let mut pages = Vec::new();
+    let mut current: Option<PageMedia> = None;
+    // Namespace of the sitemap's own elements; only its `<loc>` is the page URL
+    let mut sitemap_namespace: Option<String> = None;
+    // Element whose text we are collecting, as (namespace, local name)
+    let mut capture: Option<(Option<String>, String)> = None;
+    let mut text = String::new();
+
+    for event in EventReader::from_str(content) {
+        match event {
+            Ok(XmlEvent::StartElement { name, .. }) => {
+                if name.local_name == "url" && !is_extension(name.namespace.as_deref()) {
+                    current = Some(PageMedia {
+                        page: String::new(),
+                        assets: Vec::new(),
+                    });
+                    sitemap_namespace = name.namespace;
+                } else if current.is_some() {
+                    capture = Some((name.namespace, name.local_name));
+                    text.clear();
+                }
+            }
+            Ok(XmlEvent::Characters(chunk)) | Ok(XmlEvent::CData(chunk)) => {
+                if capture.is_some() {
+                    text.push_str(&chunk);
+                }
+            }
+            Ok(XmlEvent::EndElement { name }) => {
+                let Some(entry) = current.as_mut() else {
+                    continue;
+                };
+                if name.local_name == "url" && !is_extension(name.namespace.as_deref()) {
+                    if let Some(entry) = current.take()
+                        && !entry.page.is_empty()
+                        && !entry.assets.is_empty()
+                    {
+                        pages.push(entry);
+                    }
+                    continue;
+                }
+                let Some((namespace, local)) = capture.take() else {
+                    continue;
+                };
+                let value = text.trim().to_string();
+                if value.is_empty() {
+                    continue;
+                }
+                match (namespace.as_deref(), local.as_str()) {
+                    (Some(IMAGE_NS), "loc") => entry.assets.push((value, AssetType::Image)),
+                    (Some(VIDEO_NS), "thumbnail_loc") => {
+                        entry.assets.push((value, AssetType::Image))
+                    }
+                    (Some(VIDEO_NS), "content_loc") => entry.assets.push((value, AssetType::Media)),
+                    (namespace, "loc") if namespace == sitemap_namespace.as_deref() => {
+                        entry.page = value
+                    }
+                    _ => {}
+                }
+            }
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+
+    pages
+}
+
+fn is_extension(namespace: Option<&str>) -> bool {
+    matches!(namespace, Some(IMAGE_NS) | Some(VIDEO_NS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assets(page: &PageMedia) -> Vec<(&str, AssetType)> {
+        page.assets
+            .iter()
+            .map(|(url, kind)| (url.as_str(), *kind))
+            .collect()
+    }
+
+    #[test]
+    fn reads_image_and_video_extensions() {
+        let sitemap = r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"
+        xmlns:image="http://www.google.com/schemas/sitemap-image/1.1"
+        xmlns:video="http://www.google.com/schemas/sitemap-video/1.1">
+  <url>
+    <image:image><image:loc>https://cdn.example.net/a.jpg</image:loc></image:image>
+    <loc>https://example.com/gallery</loc>
+    <video:video>
+      <video:thumbnail_loc>https://cdn.example.net/thumb.jpg</video:thumbnail_loc>
+      <video:title>Clip</video:title>
+      <video:content_loc>https://cdn.example.net/clip.mp4</video:content_loc>
+    </video:video>
+  </url>
+</urlset>"#;
+        let pages = parse(sitemap);
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].page, "https://example.com/gallery");
+        assert_eq!(
+            assets(&pages[0]),
+            [
+                ("https://cdn.example.net/a.jpg", AssetType::Image),
+                ("https://cdn.example.net/thumb.jpg", AssetType::Image),
+                ("https://cdn.example.net/clip.mp4", AssetType::Media),
+            ]
+        );
+    }
+
+    #[test]
+    fn matches_extensions_by_namespace_not_prefix() {
+        let sitemap = r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"
+        xmlns:img="http://www.google.com/schemas/sitemap-image/1.1"
+        xmlns:v="http://www.google.com/schemas/sitemap-video/1.1"
+        xmlns:image="http://example.com/not-images">
+  <url>
+    <loc>https://example.com/page</loc>
+    <img:image><img:loc>https://example.com/photo.png</img:loc></img:image>
+    <v:video><v:content_loc>https://example.com/film.webm</v:content_loc></v:video>
+    <image:image><image:loc>https://example.com/ignored.png</image:loc></image:image>
+  </url>
+</urlset>"#;
+        let pages = parse(sitemap);
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].page, "https://example.com/page");
+        assert_eq!(
+            assets(&pages[0]),
+            [
+                ("https://example.com/photo.png", AssetType::Image),
+                ("https://example.com/film.webm", AssetType::Media),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_entries_without_media() {
+        let sitemap = r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"
+        xmlns:image="http://www.google.com/schemas/sitemap-image/1.1">
+  <url><loc>https://example.com/plain</loc><lastmod>2024-01-01</lastmod></url>
+  <url>
+    <loc> https://example.com/with-image </loc>
+    <image:image><image:loc><![CDATA[https://example.com/i.gif]]></image:loc></image:image>
+  </url>
+  <url><loc>https://example.com/also-plain</loc></url>
+</urlset>"#;
+        let pages = parse(sitemap);
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].page, "https://example.com/with-image");
+        assert_eq!(
+            assets(&pages[0]),
+            [("https://example.com/i.gif", AssetType::Image)]
+        );
+    }
+}
@@ -0,0 +1,441 @@
+use crate::Stats;
+use crate::timeseries::Bucket;
+use colored::Colorize;
+use regex::Regex;
+use std::fmt;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// A run figure a threshold can be checked against.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Metric {
+    /// Percent of requests that succeeded
+    Availability,
+    /// Percent of requests that failed
+    ErrorRate,
+    /// Fraction (0-1) of responses with a cache status header that were hits
+    CacheHitRatio,
+    /// Latency in ms
+    Latency(Latency),
+    /// Requests per second over the run
+    Rps,
+    Requests,
+    Failed,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Latency {
+    Avg,
+    Percentile(u8),
+    Max,
+}
+
+/// How the number after the operator is written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Unit {
+    Percent,
+    Fraction,
+    Millis,
+    Plain,
+}
+
+impl Metric {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "availability" => Self::Availability,
+            "error_rate" => Self::ErrorRate,
+            "cache_hit_ratio" => Self::CacheHitRatio,
+            "avg" => Self::Latency(Latency::Avg),
+            "p50" => Self::Latency(Latency::Percentile(50)),
+            "p90" => Self::Latency(Latency::Percentile(90)),
+            "p95" => Self::Latency(Latency::Percentile(95)),
+            "p99" => Self::Latency(Latency::Percentile(99)),
+            "max" => Self::Latency(Latency::Max),
+            "rps" => Self::Rps,
+            "requests" => Self::Requests,
+            "failed" => Self::Failed,
+            _ => return None,
+        })
+    }
+
+    fn unit(self) -> Unit {
+        match self {
+            Self::Availability | Self::ErrorRate => Unit::Percent,
+            Self::CacheHitRatio => Unit::Fraction,
+            Self::Latency(_) => Unit::Millis,
+            Self::Rps | Self::Requests | Self::Failed => Unit::Plain,
+        }
+    }
+
+    /// The figure for this run; None when there is nothing to measure it on.
+    fn measure(self, stats: &Stats) -> Option<f64> {
+        let requests = stats.transactions as f64;
+        match self {
+            Self::Availability => (requests > 0.0).then(|| stats.availability()),
+            Self::ErrorRate => {
+                (requests > 0.0).then(|| stats.failed_transactions as f64 / requests * 100.0)
+            }
+            Self::CacheHitRatio => {
+                let cached = stats.cache_hits + stats.cache_misses;
+                (cached > 0).then(|| stats.cache_hits as f64 / cached as f64)
+            }
+            Self::Latency(Latency::Avg) => stats.latency.avg(),
+            Self::Latency(Latency::Percentile(p)) => stats.latency.percentile(p as f64),
+            Self::Latency(Latency::Max) => stats.latency.max(),
+            Self::Rps => Some(stats.transaction_rate()),
+            Self::Requests => Some(requests),
+            Self::Failed => Some(stats.failed_transactions as f64),
+        }
+    }
+
+    /// The figure for one bucket of the run that lasted `length`; None when there is
+    /// nothing to measure it on.
+    fn measure_interval(self, bucket: &Bucket, length: Duration) -> Option<f64> {
+        match self {
+            Self::Availability => bucket.error_rate().map(|rate| 100.0 - rate),
+            Self::ErrorRate => bucket.error_rate(),
+            // Cache status is not tracked per bucket; rejected by Threshold::parse
+            Self::CacheHitRatio => None,
+            Self::Latency(Latency::Avg) => bucket.latency.avg(),
+            Self::Latency(Latency::Percentile(p)) => bucket.latency.percentile(p as f64),
+            Self::Latency(Latency::Max) => bucket.latency.max(),
+            Self::Rps => Some(bucket.requests as f64 / length.as_secs_f64()),
+            Self::Requests => Some(bucket.requests as f64),
+            Self::Failed => Some(bucket.failed as f64),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+}
+
+impl Op {
+    fn holds(self, actual: f64, limit: f64) -> bool {
+        match self {
+            Self::Lt => actual < limit,
+            Self::Le => actual <= limit,
+            Self::Gt => actual > limit,
+            Self::Ge => actual >= limit,
+            Self::Eq => (actual - limit).abs() < f64::EPSILON,
+        }
+    }
+
+    /// Whether `a` is further from passing than `b`.
+    fn worse(self, a: f64, b: f64) -> bool {
+        match self {
+            Self::Gt | Self::Ge => a < b,
+            Self::Lt | Self::Le | Self::Eq => a > b,
+        }
+    }
+}
+
+/// A pass/fail condition on the run, e.g. `p95 < 800ms` or `availability >= 99.5`.
+/// With an `interval.` prefix, e.g. `interval.error_rate < 5%`, it must hold in every
+/// time-series bucket of the run instead of over the run as a whole.
+#[derive(Clone, Debug)]
+pub struct Threshold {
+    /// The expression as written, for the results table
+    expression: String,
+    per_interval: bool,
+    metric: Metric,
+    op: Op,
+    /// Limit in the metric's own unit
+    limit: f64,
+}
+
+impl Threshold {
+    /// Parse `[interval.]<metric> <op> <number>[unit]`. Latencies take `ms` (default)
+    /// or `s`; `%` turns a number into a fraction for `cache_hit_ratio` and is optional
+    /// for the metrics that are percentages already.
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        static SYNTAX: OnceLock<Regex> = OnceLock::new();
+        let syntax = SYNTAX.get_or_init(|| {
+            Regex::new(r"^\s*(interval\.)?([a-z_0-9]+)\s*(<=|>=|==|<|>)\s*([0-9]+(?:\.[0-9]+)?)\s*(ms|s|%)?\s*$")
+                .unwrap()
+        });
+        let invalid = |reason: &str| format!("Invalid threshold '{}': {}", expression, reason);
+
+        let captures = syntax
+            .captures(expression)
+            .ok_or_else(|| invalid("expected <metric> <op> <value>, e.g. 'p95 < 800ms'"))?;
+        let per_interval = captures.get(1).is_some();
+        let metric = Metric::parse(&captures[2]).ok_or_else(|| {
+            invalid(
+                "unknown metric (use availability, error_rate, cache_hit_ratio, avg, p50, p90, p95, p99, max, rps, requests or failed)",
+            )
+        })?;
+        if per_interval && metric == Metric::CacheHitRatio {
+            return Err(invalid("cache_hit_ratio is not tracked per interval"));
+        }
+        let op = match &captures[3] {
+            "<" => Op::Lt,
+            "<=" => Op::Le,
+            ">" => Op::Gt,
+            ">=" => Op::Ge,
+            _ => Op::Eq,
+        };
+        let number: f64 = captures[4].parse().map_err(|_| invalid("bad number"))?;
+        let limit = match (metric.unit(), captures.get(5).map(|m| m.as_str())) {
+            (Unit::Millis, None | Some("ms")) => number,
+            (Unit::Millis, Some("s")) => number * 1000.0,
+            (Unit::Percent, None | Some("%")) => number,
+            (Unit::Fraction, None) => number,
+            (Unit::Fraction, Some("%")) => number / 100.0,
+            (Unit::Plain, None) => number,
+            (_, Some(unit)) => return Err(invalid(&format!("'{}' does not apply here", unit))),
+        };
+
+        Ok(Self {
+            expression: expression.trim().to_string(),
+            per_interval,
+            metric,
+            op,
+            limit,
+        })
+    }
+
+    pub fn check(&self, stats: &Stats) -> Verdict {
+        if self.per_interval {
+            return self.check_intervals(stats);
+        }
+        let actual = self.metric.measure(stats);
+        Verdict {
+            threshold: self.clone(),
+            actual,
+            at: None,
+            passed: actual.is_some_and(|actual| self.op.holds(actual, self.limit)),
+        }
+    }
+
+    /// Check every bucket and report the worst failing one, or the worst overall when
+    /// all pass. Rates are only checked on complete buckets, as the last one is cut
+    /// short by the end of the run.
+    fn check_intervals(&self, stats: &Stats) -> Verdict {
+        let series = &stats.timeline;
+        let elapsed = Duration::from_secs_f64(stats.elapsed_time());
+        let mut worst: Option<(Duration, f64)> = None;
+        let mut worst_failing: Option<(Duration, f64)> = None;
+        for (offset, length, bucket) in series.intervals(elapsed) {
+            if self.metric == Metric::Rps && length < series.width() {
+                continue;
+            }
+            let Some(value) = self.metric.measure_interval(bucket, length) else {
+                continue;
+            };
+            let slot = if self.op.holds(value, self.limit) {
+                &mut worst
+            } else {
+                &mut worst_failing
+            };
+            if slot.is_none_or(|(_, current)| self.op.worse(value, current)) {
+                *slot = Some((offset, value));
+            }
+        }
+        let (at, actual) = worst_failing.or(worst).unzip();
+        Verdict {
+            threshold: self.clone(),
+            actual,
+            at,
+            passed: worst_failing.is_none() && worst.is_some(),
+        }
+    }
+}
+
+/// The outcome of one threshold. A threshold with nothing to measure (no requests,
+/// no cache headers) fails rather than passing vacuously.
+pub struct Verdict {
+    threshold: Threshold,
+    actual: Option<f64>,
+    /// Start of the bucket `actual` was measured in, for interval thresholds
+    at: Option<Duration>,
+    pub passed: bool,
+}
+
+impl Verdict {
+    pub fn expression(&self) -> &str {
+        &self.threshold.expression
+    }
+
+    /// The measured figure with its unit, or "no data". Interval thresholds show the
+    /// worst bucket and when it started.
+    pub fn actual(&self) -> String {
+        let Some(actual) = self.actual else {
+            return "no data".to_string();
+        };
+        let value = match self.threshold.metric {
+            Metric::Availability | Metric::ErrorRate => format!("{:.2} %", actual),
+            Metric::CacheHitRatio => format!("{:.3}", actual),
+            Metric::Latency(_) => format!("{:.0} ms", actual),
+            Metric::Rps => format!("{:.2} /s", actual),
+            Metric::Requests | Metric::Failed => format!("{}", actual),
+        };
+        match self.at {
+            Some(at) => format!("{} in interval at {}s", value, at.as_secs_f64()),
+            None => value,
+        }
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "  {}  {:<30}{}",
+            if self.passed {
+                "PASS".green()
+            } else {
+                "FAIL".red()
+            },
+            self.expression(),
+            self.actual()
+        )
+    }
+}
+
+/// Check every threshold and print the results table.
+pub fn evaluate(thresholds: &[Threshold], stats: &Stats) -> Vec<Verdict> {
+    let verdicts: Vec<Verdict> = thresholds.iter().map(|t| t.check(stats)).collect();
+    if !verdicts.is_empty() {
+        println!("Thresholds:");
+        for verdict in &verdicts {
+            println!("{}", verdict);
+        }
+        println!();
+    }
+    verdicts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(requests: usize, failed: usize, latencies: &[f64]) -> Stats {
+        let mut stats = Stats {
+            transactions: requests,
+            successful_transactions: requests - failed,
+            failed_transactions: failed,
+            ..Default::default()
+        };
+        for &ms in latencies {
+            stats.latency.record(ms);
+        }
+        stats
+    }
+
+    #[test]
+    fn parses_metric_op_and_limit() {
+        let threshold = Threshold::parse(" p95 < 800ms ").unwrap();
+        assert_eq!(threshold.expression, "p95 < 800ms");
+        assert_eq!(threshold.metric, Metric::Latency(Latency::Percentile(95)));
+        assert_eq!(threshold.op, Op::Lt);
+        assert_eq!(threshold.limit, 800.0);
+        assert!(!threshold.per_interval);
+
+        let threshold = Threshold::parse("availability>=99.5").unwrap();
+        assert_eq!(threshold.metric, Metric::Availability);
+        assert_eq!(threshold.op, Op::Ge);
+        assert_eq!(threshold.limit, 99.5);
+    }
+
+    #[test]
+    fn converts_units() {
+        assert_eq!(Threshold::parse("max <= 2s").unwrap().limit, 2000.0);
+        assert_eq!(Threshold::parse("avg < 300").unwrap().limit, 300.0);
+        assert_eq!(Threshold::parse("error_rate < 1%").unwrap().limit, 1.0);
+        assert_eq!(
+            Threshold::parse("cache_hit_ratio > 90%").unwrap().limit,
+            0.9
+        );
+        assert_eq!(
+            Threshold::parse("cache_hit_ratio > 0.9").unwrap().limit,
+            0.9
+        );
+    }
+
+    #[test]
+    fn parses_interval_prefix() {
+        let threshold = Threshold::parse("interval.error_rate < 5%").unwrap();
+        assert!(threshold.per_interval);
+        assert_eq!(threshold.metric, Metric::ErrorRate);
+        assert!(Threshold::parse("interval.p99 < 1s").unwrap().per_interval);
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        for expression in [
+            "",
+            "p95",
+            "p95 < fast",
+            "p95 != 800",
+            "p97 < 800ms",
+            "rps > 10ms",
+            "requests > 5%",
+            "availability > 99s",
+            "window.p95 < 800",
+        ] {
+            let error = Threshold::parse(expression).unwrap_err();
+            assert!(
+                error.starts_with("Invalid threshold"),
+                "{}: {}",
+                expression,
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_cache_hit_ratio_per_interval() {
+        let error = Threshold::parse("interval.cache_hit_ratio > 0.5").unwrap_err();
+        assert!(error.contains("not tracked per interval"), "{}", error);
+    }
+
+    #[test]
+    fn checks_run_figures() {
+        let stats = stats(200, 2, &[100.0, 200.0, 300.0]);
+        assert!(
+            Threshold::parse("error_rate < 2%")
+                .unwrap()
+                .check(&stats)
+                .passed
+        );
+        assert!(
+            !Threshold::parse("error_rate < 1%")
+                .unwrap()
+                .check(&stats)
+                .passed
+        );
+        assert!(
+            Threshold::parse("failed == 2")
+                .unwrap()
+                .check(&stats)
+                .passed
+        );
+        assert!(
+            Threshold::parse("max <= 300ms")
+                .unwrap()
+                .check(&stats)
+                .passed
+        );
+        assert!(!Threshold::parse("avg < 0.1s").unwrap().check(&stats).passed);
+    }
+
+    #[test]
+    fn fails_without_data() {
+        let stats = stats(0, 0, &[]);
+        let verdict = Threshold::parse("p95 < 800ms").unwrap().check(&stats);
+        assert!(!verdict.passed);
+        assert_eq!(verdict.actual(), "no data");
+        assert!(
+            !Threshold::parse("cache_hit_ratio > 0")
+                .unwrap()
+                .check(&stats)
+                .passed
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use crate::RequestResult;
+use crate::histogram::Histogram;
+use std::time::Duration;
+
+/// Requests that finished during one bucket of the run.
+#[derive(Clone, Debug, Default)]
+pub struct Bucket {
+    pub requests: usize,
+    /// Error responses (4xx/5xx) and requests that got no response
+    pub failed: usize,
+    /// Requests that got no response
+    pub errors: usize,
+    pub bytes: u64,
+    pub latency: Histogram,
+}
+
+impl Bucket {
+    /// Percent of requests that failed; None for a bucket without requests.
+    pub fn error_rate(&self) -> Option<f64> {
+        (self.requests > 0).then(|| self.failed as f64 / self.requests as f64 * 100.0)
+    }
+
+    fn merge(&mut self, other: &Bucket) {
+        self.requests += other.requests;
+        self.failed += other.failed;
+        self.errors += other.errors;
+        self.bytes += other.bytes;
+        self.latency.merge(&other.latency);
+    }
+}
+
+/// Requests, failures, bytes and latency per fixed-width slice of the run, so a
+/// collapse late in the run stays visible next to the cumulative totals.
+#[derive(Clone, Debug)]
+pub struct TimeSeries {
+    width: Duration,
+    buckets: Vec<Bucket>,
+}
+
+impl Default for TimeSeries {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(1))
+    }
+}
+
+impl TimeSeries {
+    pub fn new(width: Duration) -> Self {
+        Self {
+            width: width.max(Duration::from_millis(1)),
+            buckets: Vec::new(),
+        }
+    }
+
+    pub fn width(&self) -> Duration {
+        self.width
+    }
+
+    pub fn buckets(&self) -> &[Bucket] {
+        &self.buckets
+    }
+
+    /// Buckets with their offset from the start of the run and how long they lasted.
+    /// The last bucket is cut off at `elapsed`, so its rate is not understated.
+    pub fn intervals(
+        &self,
+        elapsed: Duration,
+    ) -> impl Iterator<Item = (Duration, Duration, &Bucket)> {
+        self.buckets.iter().enumerate().map(move |(i, bucket)| {
+            let offset = self.width * i as u32;
+            let length = elapsed.saturating_sub(offset).min(self.width);
+            (offset, length, bucket)
+        })
+    }
+
+    /// Count `result` in the bucket covering `offset` from the start of the run.
+    pub fn record(&mut self, offset: Duration, result: &RequestResult) {
+        let index = (offset.as_nanos() / self.width.as_nanos()) as usize;
+        if self.buckets.len() <= index {
+            self.buckets.resize_with(index + 1, Bucket::default);
+        }
+        let bucket = &mut self.buckets[index];
+        bucket.requests += 1;
+        if result.error.is_some() {
+            bucket.errors += 1;
+            bucket.failed += 1;
+        } else if result.status_code >= 400 {
+            bucket.failed += 1;
+        }
+        bucket.bytes += result.data_size;
+        bucket.latency.record(result.response_time);
+    }
+
+    /// The same series with neighbouring buckets merged so there are at most
+    /// `max_buckets`, for charts of long runs.
+    pub fn downsample(&self, max_buckets: usize) -> TimeSeries {
+        let factor = self.buckets.len().div_ceil(max_buckets.max(1)).max(1);
+        if factor == 1 {
+            return self.clone();
+        }
+        let buckets = self
+            .buckets
+            .chunks(factor)
+            .map(|chunk| {
+                let mut merged = Bucket::default();
+                for bucket in chunk {
+                    merged.merge(bucket);
+                }
+                merged
+            })
+            .collect();
+        TimeSeries {
+            width: self.width * factor as u32,
+            buckets,
+        }
+    }
+}
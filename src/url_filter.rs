@@ -0,0 +1,190 @@
+use regex::Regex;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use url::Url;
+
+/// One compiled `--include` / `--exclude` pattern.
+///
+/// Patterns prefixed with `re:` are regular expressions searched anywhere in the full URL.
+/// Anything else is a glob where `*` matches any run of characters (including `/` and `?`)
+/// and must match the whole target: the path and query when the glob starts with `/`,
+/// otherwise the full URL.
+struct Pattern {
+    regex: Regex,
+    path_only: bool,
+}
+
+impl Pattern {
+    fn parse(raw: &str) -> Result<Self, String> {
+        if let Some(expr) = raw.strip_prefix("re:") {
+            return Regex::new(expr)
+                .map(|regex| Self {
+                    regex,
+                    path_only: false,
+                })
+                .map_err(|e| format!("Invalid URL pattern '{}': {}", raw, e));
+        }
+
+        let body: Vec<String> = raw.split('*').map(regex::escape).collect();
+        Regex::new(&format!("^{}$", body.join(".*")))
+            .map(|regex| Self {
+                regex,
+                path_only: raw.starts_with('/'),
+            })
+            .map_err(|e| format!("Invalid URL pattern '{}': {}", raw, e))
+    }
+
+    fn matches(&self, url: &str) -> bool {
+        if !self.path_only {
+            return self.regex.is_match(url);
+        }
+        match Url::parse(url) {
+            Ok(parsed) => {
+                let mut target = parsed.path().to_string();
+                if let Some(query) = parsed.query() {
+                    target.push('?');
+                    target.push_str(query);
+                }
+                self.regex.is_match(&target)
+            }
+            Err(_) => self.regex.is_match(url),
+        }
+    }
+}
+
+/// Include/exclude filter applied to every URL source (sitemaps, follow-links,
+/// the JS crawler and asset lists). Includes select which pages get warmed;
+/// excludes drop pages and assets alike and also prune the crawl frontier.
+pub struct UrlFilter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+    /// Every distinct URL dropped by the filter, for the summary count.
+    filtered: Mutex<HashSet<String>>,
+}
+
+impl UrlFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self, String> {
+        Ok(Self {
+            include: include
+                .iter()
+                .map(|p| Pattern::parse(p))
+                .collect::<Result<_, _>>()?,
+            exclude: exclude
+                .iter()
+                .map(|p| Pattern::parse(p))
+                .collect::<Result<_, _>>()?,
+            filtered: Mutex::new(HashSet::new()),
+        })
+    }
+
+    /// Whether a page should be warmed: it matches an include (when any are set)
+    /// and no exclude.
+    pub fn allows(&self, url: &str) -> bool {
+        let included = self.include.is_empty() || self.include.iter().any(|p| p.matches(url));
+        if included && !self.matches_exclude(url) {
+            return true;
+        }
+        self.record(url);
+        false
+    }
+
+    /// Whether an asset or crawl-frontier URL is excluded. Includes are not applied
+    /// here so that crawlers can still pass through pages outside the include set.
+    pub fn is_excluded(&self, url: &str) -> bool {
+        if self.matches_exclude(url) {
+            self.record(url);
+            return true;
+        }
+        false
+    }
+
+    pub fn filtered_count(&self) -> usize {
+        self.filtered.lock().unwrap().len()
+    }
+
+    fn matches_exclude(&self, url: &str) -> bool {
+        self.exclude.iter().any(|p| p.matches(url))
+    }
+
+    fn record(&self, url: &str) {
+        self.filtered.lock().unwrap().insert(url.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(include: &[&str], exclude: &[&str]) -> UrlFilter {
+        let strings =
+            |patterns: &[&str]| patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        UrlFilter::new(&strings(include), &strings(exclude)).unwrap()
+    }
+
+    #[test]
+    fn path_globs_match_path_and_query() {
+        let filter = filter(&["/blog/*"], &[]);
+        assert!(filter.allows("https://example.com/blog/post"));
+        assert!(filter.allows("https://example.com/blog/2024/post?page=2"));
+        assert!(!filter.allows("https://example.com/shop/blog/post"));
+        assert!(!filter.allows("https://example.com/blog"));
+    }
+
+    #[test]
+    fn globs_must_match_the_whole_target() {
+        let filter = filter(&["/products"], &["*?sort=*"]);
+        assert!(filter.allows("https://example.com/products"));
+        assert!(!filter.allows("https://example.com/products/shoes"));
+        assert!(!filter.allows("https://example.com/products?sort=price"));
+    }
+
+    #[test]
+    fn full_url_globs_match_scheme_and_host() {
+        let filter = filter(&["https://shop.example.com/*"], &[]);
+        assert!(filter.allows("https://shop.example.com/cart"));
+        assert!(!filter.allows("https://example.com/cart"));
+        assert!(!filter.allows("http://shop.example.com/cart"));
+    }
+
+    #[test]
+    fn glob_metacharacters_are_literal() {
+        let filter = filter(&[], &["/search?q=*", "*.pdf"]);
+        assert!(!filter.allows("https://example.com/search?q=shoes"));
+        assert!(filter.allows("https://example.com/searchXq=shoes"));
+        assert!(!filter.allows("https://example.com/files/report.pdf"));
+        assert!(filter.allows("https://example.com/files/reportxpdf"));
+    }
+
+    #[test]
+    fn regex_patterns_search_the_full_url() {
+        let filter = filter(&["re:/(en|de)/"], &[r"re:\.(jpe?g|png)$"]);
+        assert!(filter.allows("https://example.com/en/about"));
+        assert!(!filter.allows("https://example.com/fr/about"));
+        assert!(!filter.allows("https://example.com/de/logo.png"));
+        assert!(UrlFilter::new(&["re:(".to_string()], &[]).is_err());
+    }
+
+    #[test]
+    fn excludes_win_over_includes() {
+        let filter = filter(&["/docs/*"], &["/docs/internal/*"]);
+        assert!(filter.allows("https://example.com/docs/start"));
+        assert!(!filter.allows("https://example.com/docs/internal/keys"));
+    }
+
+    #[test]
+    fn is_excluded_ignores_includes() {
+        let filter = filter(&["/blog/*"], &["*/admin/*"]);
+        assert!(!filter.is_excluded("https://example.com/about"));
+        assert!(filter.is_excluded("https://example.com/admin/login"));
+    }
+
+    #[test]
+    fn counts_each_filtered_url_once() {
+        let filter = filter(&["/blog/*"], &["*.zip"]);
+        filter.allows("https://example.com/about");
+        filter.allows("https://example.com/about");
+        filter.is_excluded("https://example.com/a.zip");
+        filter.allows("https://example.com/blog/post");
+        assert_eq!(filter.filtered_count(), 2);
+    }
+}
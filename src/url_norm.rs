@@ -0,0 +1,129 @@
+use scraper::{Html, Selector};
+use url::Url;
+
+/// Base URL that relative references in a document resolve against: the `<base href>`
+/// when present (itself resolved against the page), otherwise the page URL.
+pub fn document_base(html: &Html, page_url: &str) -> Option<Url> {
+    let page = Url::parse(page_url).ok()?;
+    let base_href = Selector::parse("base[href]").ok().and_then(|selector| {
+        html.select(&selector)
+            .next()
+            .and_then(|base| base.value().attr("href"))
+            .and_then(|href| page.join(href.trim()).ok())
+    });
+    Some(base_href.unwrap_or(page))
+}
+
+/// Resolve an `href`/`src` value against `base` the way a browser does
+/// (`//host/x`, `../x`, `x`, `?q` ...). Returns `None` for empty references, in-page
+/// anchors, data URIs and non-HTTP schemes such as `javascript:`, `mailto:` and `tel:`.
+/// The fragment is dropped since it never reaches the server.
+pub fn resolve(reference: &str, base: &Url) -> Option<String> {
+    let reference = reference.trim();
+    if reference.is_empty() || reference.starts_with('#') {
+        return None;
+    }
+    let mut resolved = base.join(reference).ok()?;
+    if resolved.scheme() != "http" && resolved.scheme() != "https" {
+        return None;
+    }
+    resolved.set_fragment(None);
+    Some(resolved.into())
+}
+
+/// Canonical form of a URL for deduplication: lowercase scheme and host, default ports
+/// and dot segments removed (all done by `Url::parse`), no fragment and no empty query.
+/// With `sort_query`, query parameters are sorted so `?b=2&a=1` and `?a=1&b=2` are
+/// the same page. Strings that don't parse as URLs are returned unchanged.
+pub fn normalize(url: &str, sort_query: bool) -> String {
+    let Ok(mut parsed) = Url::parse(url.trim()) else {
+        return url.to_string();
+    };
+    parsed.set_fragment(None);
+
+    match parsed.query() {
+        Some("") => parsed.set_query(None),
+        Some(query) if sort_query => {
+            let mut pairs: Vec<&str> = query.split('&').filter(|p| !p.is_empty()).collect();
+            pairs.sort();
+            let sorted = pairs.join("&");
+            parsed.set_query(Some(&sorted));
+        }
+        _ => {}
+    }
+
+    parsed.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_lowercases_scheme_and_host_and_drops_default_port() {
+        assert_eq!(
+            normalize("HTTPS://Example.COM:443/Path", false),
+            "https://example.com/Path"
+        );
+        assert_eq!(
+            normalize("http://example.com:80/", false),
+            "http://example.com/"
+        );
+        assert_eq!(
+            normalize("http://example.com:8080/", false),
+            "http://example.com:8080/"
+        );
+    }
+
+    #[test]
+    fn normalize_resolves_dot_segments_and_drops_fragment_and_empty_query() {
+        assert_eq!(
+            normalize("https://example.com/a/./b/../c#top", false),
+            "https://example.com/a/c"
+        );
+        assert_eq!(
+            normalize("https://example.com/page?", false),
+            "https://example.com/page"
+        );
+        assert_eq!(
+            normalize("  https://example.com  ", false),
+            "https://example.com/"
+        );
+    }
+
+    #[test]
+    fn normalize_sorts_query_only_when_asked() {
+        let url = "https://example.com/p?b=2&a=1&&c=3";
+        assert_eq!(normalize(url, false), url);
+        assert_eq!(normalize(url, true), "https://example.com/p?a=1&b=2&c=3");
+    }
+
+    #[test]
+    fn normalize_leaves_unparsable_strings_alone() {
+        assert_eq!(normalize("not a url", true), "not a url");
+    }
+
+    #[test]
+    fn resolve_follows_browser_rules() {
+        let base = Url::parse("https://example.com/dir/page.html").unwrap();
+        assert_eq!(
+            resolve("other.html#x", &base).as_deref(),
+            Some("https://example.com/dir/other.html")
+        );
+        assert_eq!(
+            resolve("../up", &base).as_deref(),
+            Some("https://example.com/up")
+        );
+        assert_eq!(
+            resolve("//cdn.example.net/a.js", &base).as_deref(),
+            Some("https://cdn.example.net/a.js")
+        );
+        assert_eq!(
+            resolve("?q=1", &base).as_deref(),
+            Some("https://example.com/dir/page.html?q=1")
+        );
+        for skipped in ["", "#top", "javascript:void(0)", "mailto:a@b.c", "data:x"] {
+            assert_eq!(resolve(skipped, &base), None, "{}", skipped);
+        }
+    }
+}